@@ -1,3 +1,6 @@
+// `Option::map`で要素を書き換える書き方をそのまま示すため、clippyの指摘は抑える
+#![allow(clippy::option_map_unit_fn)]
+
 use toy_vec::ToyVec;
 
 fn main() {
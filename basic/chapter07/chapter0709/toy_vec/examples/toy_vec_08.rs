@@ -0,0 +1,43 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use toy_vec::interner::Symbol;
+use toy_vec::ToyInterner;
+
+thread_local!(
+    // 文字列の実体はインターナがまとめて持つ
+    static NAMES: RefCell<ToyInterner> = RefCell::new(ToyInterner::new());
+    // レジストリには`&'static str`ではなく`Symbol`を格納するので、実行時に作った名前も登録できる
+    static RABBITS: RefCell<HashSet<Symbol>> = RefCell::new(HashSet::new());
+);
+
+fn register(name: &str) {
+    let symbol = NAMES.with(|names| names.borrow_mut().intern(name));
+    RABBITS.with(|rb| rb.borrow_mut().insert(symbol));
+}
+
+fn contains(name: &str) -> bool {
+    // インターンされていない名前は登録されているはずがない
+    match NAMES.with(|names| names.borrow().get(name)) {
+        Some(symbol) => RABBITS.with(|rb| rb.borrow().contains(&symbol)),
+        None => false,
+    }
+}
+
+fn main() {
+    register("ロップイヤー");
+    register("ダッチ");
+
+    // 実行時に組み立てた名前（String型）も登録できる
+    let breed = format!("{}・{}", "ネザーランド", "ドワーフ");
+    register(&breed);
+
+    assert!(contains("ロップイヤー"));
+    assert!(contains("ネザーランド・ドワーフ"));
+    assert!(!contains("ドワーフホト"));
+
+    NAMES.with(|names| {
+        for (symbol, name) in names.borrow().iter() {
+            println!("{:?} => {}", symbol, name);
+        }
+    });
+}
@@ -7,5 +7,6 @@ cargo run --example toy_vec_04
 cargo run --example toy_vec_05
 cargo run --example toy_vec_06
 cargo run --example toy_vec_07
+cargo run --example toy_vec_08
 
 cargo clean
@@ -0,0 +1,58 @@
+/*
+ * toy_vec.h: C言語から`ToyVec`を使うための宣言
+ *
+ * libtoy_vec.a（staticlib）またはlibtoy_vec.so（cdylib）とリンクして使う
+ * ライブラリは ffi フィーチャーを有効にして作る:
+ *   cargo rustc --release --lib --features ffi --crate-type staticlib
+ * 静的リンクする場合は`-lpthread -ldl -lm`も必要
+ *
+ * 所有権の規則:
+ * - toyvec_*_new が返したポインタは呼び出し側が所有し、同じ型の toyvec_*_free で一度だけ解放する
+ * - toyvec_*_as_ptr が返すポインタは借用で、解放してはならない
+ *   次に push / extend / free を呼ぶまでの間だけ有効
+ * - 同じ ToyVec を複数のスレッドから同時に使ってはならない
+ * - 引数のポインタが NULL なら何もせず、false や 0 を返す
+ */
+#ifndef TOY_VEC_H
+#define TOY_VEC_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* int32_t を要素とする ToyVec。中身は不透明 */
+typedef struct ToyVecI32 ToyVecI32;
+
+ToyVecI32 *toyvec_i32_new(void);
+void toyvec_i32_free(ToyVecI32 *vec);
+void toyvec_i32_push(ToyVecI32 *vec, int32_t value);
+/* 末尾の要素を取り除いて *out に書き込む。空なら false */
+bool toyvec_i32_pop(ToyVecI32 *vec, int32_t *out);
+/* index 番目の要素を *out に書き込む。範囲外なら false */
+bool toyvec_i32_get(const ToyVecI32 *vec, size_t index, int32_t *out);
+size_t toyvec_i32_len(const ToyVecI32 *vec);
+/* 先頭の要素へのポインタ。len が 0 なら読んではならない */
+const int32_t *toyvec_i32_as_ptr(const ToyVecI32 *vec);
+
+/* バイト列（uint8_t）を要素とする ToyVec。中身は不透明 */
+typedef struct ToyVecBytes ToyVecBytes;
+
+ToyVecBytes *toyvec_bytes_new(void);
+void toyvec_bytes_free(ToyVecBytes *vec);
+void toyvec_bytes_push(ToyVecBytes *vec, uint8_t value);
+/* data から len バイトを末尾に追加する */
+void toyvec_bytes_extend(ToyVecBytes *vec, const uint8_t *data, size_t len);
+bool toyvec_bytes_pop(ToyVecBytes *vec, uint8_t *out);
+bool toyvec_bytes_get(const ToyVecBytes *vec, size_t index, uint8_t *out);
+size_t toyvec_bytes_len(const ToyVecBytes *vec);
+const uint8_t *toyvec_bytes_as_ptr(const ToyVecBytes *vec);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* TOY_VEC_H */
@@ -0,0 +1,154 @@
+// `ToyVec`の上に作ったスタックとキュー
+use crate::ToyVec;
+use std::mem;
+
+// 後入れ先出し（LIFO）のスタック。末尾への追加と削除だけを許す
+pub struct ToyStack<T> {
+    elements: ToyVec<T>,
+}
+
+impl<T: Default> ToyStack<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            elements: ToyVec::with_capacity(capacity),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    pub fn push(&mut self, element: T) {
+        self.elements.push(element);
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.elements.pop()
+    }
+
+    // 次に`pop`で取り出される要素
+    pub fn peek(&self) -> Option<&T> {
+        self.elements.as_slice().last()
+    }
+}
+
+impl<T: Default> Default for ToyStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 先入れ先出し（FIFO）のキュー
+// 取り出した要素の位置は`head`より前の空き領域として残し、空き領域が要素数より多くなったら
+// 残りの要素を先頭へ詰める。詰めるのはまとめて行うので、取り出しはならしてO(1)になる
+// このクレートには両端から出し入れできるリングバッファ（`VecDeque`に当たるもの）がないので、
+// `ToyVec`の上にこの方法で作っている。詰める間は要素数に比例する時間がかかる
+pub struct ToyQueue<T> {
+    elements: ToyVec<T>,
+    head: usize,
+}
+
+impl<T: Default> ToyQueue<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            elements: ToyVec::with_capacity(capacity),
+            head: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len() - self.head
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn push_back(&mut self, element: T) {
+        self.elements.push(element);
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let element = mem::take(self.elements.get_mut(self.head)?);
+        self.head += 1;
+        if self.head * 2 >= self.elements.len() {
+            self.compact();
+        }
+        Some(element)
+    }
+
+    // 次に`pop_front`で取り出される要素
+    pub fn peek(&self) -> Option<&T> {
+        self.elements.get(self.head)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.elements.as_slice()[self.head..].iter()
+    }
+
+    // 取り出し済みの空き領域を末尾へ回し、`pop`で取り除く
+    fn compact(&mut self) {
+        self.elements.as_mut_slice().rotate_left(self.head);
+        for _ in 0..self.head {
+            self.elements.pop();
+        }
+        self.head = 0;
+    }
+}
+
+impl<T: Default> Default for ToyQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ToyQueue, ToyStack};
+
+    #[test]
+    fn test_stack() {
+        let mut stack = ToyStack::new();
+        stack.push(1);
+        stack.push(2);
+        assert_eq!(stack.peek(), Some(&2));
+        assert_eq!(stack.pop(), Some(2));
+        assert_eq!(stack.pop(), Some(1));
+        assert_eq!(stack.pop(), None);
+    }
+
+    #[test]
+    fn test_queue() {
+        let mut queue = ToyQueue::new();
+        let mut expected = 0;
+        // 追加と取り出しを交互に繰り返しても、順序が保たれる
+        for i in 0..1000 {
+            queue.push_back(i);
+            if i % 3 == 0 {
+                assert_eq!(queue.pop_front(), Some(expected));
+                expected += 1;
+            }
+        }
+        assert_eq!(queue.len(), 1000 - expected);
+        assert_eq!(queue.peek(), Some(&expected));
+        assert!(queue.iter().copied().eq(expected..1000));
+        while let Some(i) = queue.pop_front() {
+            assert_eq!(i, expected);
+            expected += 1;
+        }
+        assert_eq!(expected, 1000);
+        assert!(queue.is_empty());
+    }
+}
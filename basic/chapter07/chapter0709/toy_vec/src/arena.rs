@@ -0,0 +1,252 @@
+use crate::ToyVec;
+use std::cell::RefCell;
+
+// 最初のチャンクのキャパシティ。以降のチャンクは直前の2倍にする
+const MIN_CHUNK_CAPACITY: usize = 8;
+
+// 型付きのアリーナアロケータ。確保した要素への参照はアリーナが生存している間ずっと有効
+// `ToyVec`と違って既存の要素を新しい領域へムーブしないので、アリーナが伸びても参照は無効にならない
+pub struct ToyArena<T> {
+    chunks: RefCell<ChunkList<T>>,
+}
+
+struct ChunkList<T> {
+    // 確保済みのチャンク。各チャンクは作成後にキャパシティを変えないので、要素の位置は動かない
+    // `ToyVec`自体を`Box`に入れておくことで、外側の`ToyVec`が伸びても
+    // チャンクの領域を指す`Box<[T]>`はムーブされない
+    chunks: ToyVec<Box<ToyVec<T>>>,
+    // 現在のチャンクの先頭を指す生ポインタ
+    // 要素への書き込みは必ずこのポインタ経由で行い、チャンクの`ToyVec`には触れない
+    // （`ToyVec`経由で書き込むとスライス全体への可変の参照が作られ、
+    //   すでに返した参照と競合してしまう）
+    current: *mut T,
+    // 現在のチャンクで使用済みの要素数とキャパシティ
+    used: usize,
+    capacity: usize,
+    // 全チャンクで確保した要素数
+    len: usize,
+}
+
+impl<T: Default> ToyArena<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(MIN_CHUNK_CAPACITY)
+    }
+
+    // 最初のチャンクのキャパシティを指定してアリーナを作る
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut chunks = ChunkList {
+            chunks: ToyVec::new(),
+            current: std::ptr::null_mut(),
+            used: 0,
+            capacity: 0,
+            len: 0,
+        };
+        chunks.add_chunk(capacity.max(1));
+        Self {
+            chunks: RefCell::new(chunks),
+        }
+    }
+
+    // 値をアリーナへムーブし、その値への可変の参照を返す
+    // `&self`で呼べるので、確保済みの参照を保持したまま新たに確保できる
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc(&self, value: T) -> &mut T {
+        let mut chunks = self.chunks.borrow_mut();
+        chunks.reserve(1);
+        let slot = chunks.next_slot();
+        unsafe {
+            // 確保時に詰めておいたデフォルト値を取り出して破棄し、代わりに`value`を置く
+            drop(std::ptr::replace(slot, value));
+            // 各スロットは一度しか返さず、アリーナが生存している間は解放も移動もされない
+            &mut *slot
+        }
+    }
+
+    // イテレータの要素をまとめて連続した領域へ確保し、スライスとして返す
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_extend<I>(&self, iter: I) -> &mut [T]
+    where
+        I: IntoIterator<Item = T>,
+    {
+        // 要素数を知るために一旦`ToyVec`へ集める
+        // イテレータの中で同じアリーナへ確保されても困らないよう、借用する前に集めておく
+        let mut values = ToyVec::new();
+        for value in iter {
+            values.push(value);
+        }
+        let n = values.len();
+        if n == 0 {
+            return &mut [];
+        }
+
+        let mut chunks = self.chunks.borrow_mut();
+        chunks.reserve(n);
+        let start = chunks.next_slot();
+        for (i, value) in values.into_iter().enumerate() {
+            unsafe {
+                let slot = start.add(i);
+                drop(std::ptr::replace(slot, value));
+            }
+        }
+        // `next_slot`で1つ進めたので残りの`n - 1`個を使用済みにする
+        chunks.used += n - 1;
+        chunks.len += n - 1;
+        unsafe { std::slice::from_raw_parts_mut(start, n) }
+    }
+
+    // これまでに確保した要素数を返す
+    pub fn len(&self) -> usize {
+        self.chunks.borrow().len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // 確保したチャンクの数を返す
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.borrow().chunks.len()
+    }
+}
+
+impl<T: Default> Default for ToyArena<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Default> ChunkList<T> {
+    // 現在のチャンクに`additional`個の空きがなければ新しいチャンクを追加する
+    fn reserve(&mut self, additional: usize) {
+        if self.capacity - self.used < additional {
+            let next = (self.capacity * 2).max(additional);
+            self.add_chunk(next);
+        }
+    }
+
+    fn add_chunk(&mut self, capacity: usize) {
+        // `ToyVec`のキャパシティ分の領域はデフォルト値で埋められている
+        let mut chunk = Box::new(ToyVec::with_capacity(capacity));
+        // 先頭へのポインタは、まだ誰も要素を参照していないこの時点で一度だけ取得する
+        self.current = chunk.elements.as_mut_ptr();
+        self.used = 0;
+        self.capacity = capacity;
+        self.chunks.push(chunk);
+    }
+
+    // 現在のチャンクの次の空きスロットを返し、使用済みにする
+    fn next_slot(&mut self) -> *mut T {
+        debug_assert!(self.used < self.capacity);
+        let slot = unsafe { self.current.add(self.used) };
+        self.used += 1;
+        self.len += 1;
+        slot
+    }
+}
+
+// アリーナ自身は`Drop`を実装しない
+// チャンクを破棄すると各`ToyVec`が保持する`Box<[T]>`がまとめて解放される
+// また`Drop`を実装しないことで、要素同士がアリーナ内の参照を持ち合う（循環する）構造も作れる
+
+// 以下、テスト
+// アリーナはunsafeなコードを含むので、`cargo +nightly miri test arena`でも確認できるように
+// Miri上では反復回数を減らしている
+#[cfg(test)]
+mod tests {
+    use super::ToyArena;
+    use std::cell::Cell;
+
+    #[cfg(not(miri))]
+    const N: usize = 10_000;
+    #[cfg(miri)]
+    const N: usize = 100;
+
+    #[test]
+    fn test_references_survive_growth() {
+        let arena = ToyArena::with_capacity(1);
+        let mut refs = Vec::new();
+        for i in 0..N {
+            refs.push(arena.alloc(i));
+        }
+        // チャンクが何度追加されても、最初に確保した参照は有効なまま
+        assert!(arena.chunk_count() > 1);
+        for (i, r) in refs.iter_mut().enumerate() {
+            assert_eq!(**r, i);
+            **r += 1;
+        }
+        assert_eq!(*refs[0], 1);
+        assert_eq!(arena.len(), N);
+    }
+
+    #[test]
+    fn test_alloc_extend() {
+        let arena = ToyArena::with_capacity(4);
+        let first = arena.alloc("Java Finch".to_string());
+        let birds = arena.alloc_extend(vec!["Budgerigar".to_string(), "Canary".to_string()]);
+        // 現在のチャンクに収まらないので新しいチャンクに確保される
+        let many = arena.alloc_extend((0..10).map(|i| i.to_string()));
+        let empty = arena.alloc_extend(Vec::new());
+
+        assert_eq!(first, "Java Finch");
+        assert_eq!(birds, &["Budgerigar", "Canary"]);
+        assert_eq!(many.len(), 10);
+        assert_eq!(many[9], "9");
+        assert!(empty.is_empty());
+        assert_eq!(arena.len(), 13);
+    }
+
+    // 要素同士が参照し合うグラフを作れる
+    #[derive(Default)]
+    struct Node<'a> {
+        value: i32,
+        next: Cell<Option<&'a Node<'a>>>,
+    }
+
+    #[test]
+    fn test_cyclic_references() {
+        let arena = ToyArena::new();
+        let a: &Node = arena.alloc(Node {
+            value: 1,
+            next: Cell::new(None),
+        });
+        let b: &Node = arena.alloc(Node {
+            value: 2,
+            next: Cell::new(Some(a)),
+        });
+        a.next.set(Some(b));
+
+        assert_eq!(a.next.get().map(|n| n.value), Some(2));
+        assert_eq!(
+            b.next.get().and_then(|n| n.next.get()).map(|n| n.value),
+            Some(2)
+        );
+    }
+
+    // 破棄された回数を数える。デフォルト値（`None`）は数えない
+    #[derive(Default)]
+    struct DropCounter<'a>(Option<&'a Cell<usize>>);
+
+    impl<'a> Drop for DropCounter<'a> {
+        fn drop(&mut self) {
+            if let Some(count) = self.0 {
+                count.set(count.get() + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_drop_all_at_once() {
+        let count = Cell::new(0);
+        {
+            let arena = ToyArena::with_capacity(2);
+            for _ in 0..5 {
+                arena.alloc(DropCounter(Some(&count)));
+            }
+            arena.alloc_extend((0..3).map(|_| DropCounter(Some(&count))));
+            // アリーナが生存している間は破棄されない
+            assert_eq!(count.get(), 0);
+        }
+        // アリーナの破棄とともに全要素が一度ずつ破棄される
+        assert_eq!(count.get(), 8);
+    }
+}
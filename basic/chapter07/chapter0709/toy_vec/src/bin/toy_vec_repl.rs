@@ -0,0 +1,186 @@
+// `ToyVec`の中身を対話的に調べるためのプログラム
+//
+// 標準入力からコマンドを1行ずつ読み、実行するたびに長さ、キャパシティ、
+// `elements`の使用中の領域（#）と空き領域（.）の図を表示する
+//
+//   cargo run --bin toy_vec_repl
+//   cargo run --bin toy_vec_repl -- --script tests/repl/growth.txt
+//
+// `--script`を指定するとファイルからコマンドを読み、各コマンドを`> `に続けて表示する
+// スクリプトでは空行と`#`で始まる行を読み飛ばす
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process;
+use toy_vec::ToyVec;
+
+// 図に描く領域の最大数。これより多い分は省略する
+const MAX_DIAGRAM_SLOTS: usize = 64;
+
+const HELP: &str =
+    "commands: push <n>, pop, get <index>, reserve <n>, shrink, iter, dump, help, quit";
+
+enum Command {
+    Push(i64),
+    Pop,
+    Get(usize),
+    Reserve(usize),
+    Shrink,
+    Iter,
+    Dump,
+    Help,
+    Quit,
+}
+
+fn parse(line: &str) -> Result<Command, String> {
+    let mut words = line.split_whitespace();
+    let name = words.next().unwrap_or("");
+    let arg = words.next();
+    if words.next().is_some() {
+        return Err(format!("too many arguments: `{}`", line));
+    }
+    let command = match (name, arg) {
+        ("push", Some(n)) => Command::Push(parse_arg(n)?),
+        ("get", Some(i)) => Command::Get(parse_arg(i)?),
+        ("reserve", Some(n)) => Command::Reserve(parse_arg(n)?),
+        ("push", None) | ("get", None) | ("reserve", None) => {
+            return Err(format!("`{}` needs an argument", name))
+        }
+        (_, Some(_)) if ["pop", "shrink", "iter", "dump", "help", "quit"].contains(&name) => {
+            return Err(format!("`{}` takes no arguments", name))
+        }
+        ("pop", None) => Command::Pop,
+        ("shrink", None) => Command::Shrink,
+        ("iter", None) => Command::Iter,
+        ("dump", None) => Command::Dump,
+        ("help", None) => Command::Help,
+        ("quit", None) | ("exit", None) => Command::Quit,
+        _ => return Err(format!("unknown command `{}` (try `help`)", name)),
+    };
+    Ok(command)
+}
+
+fn parse_arg<T: std::str::FromStr>(arg: &str) -> Result<T, String> {
+    arg.parse().map_err(|_| format!("invalid number `{}`", arg))
+}
+
+// コマンドを実行し、結果を`out`に書く。`quit`なら`false`を返す
+fn execute<W: Write>(vec: &mut ToyVec<i64>, command: Command, out: &mut W) -> io::Result<bool> {
+    match command {
+        Command::Push(n) => vec.push(n),
+        Command::Pop => match vec.pop() {
+            Some(n) => writeln!(out, "popped {}", n)?,
+            None => writeln!(out, "empty")?,
+        },
+        Command::Get(i) => match vec.get(i) {
+            Some(n) => writeln!(out, "[{}] = {}", i, n)?,
+            None => writeln!(out, "index {} is out of bounds", i)?,
+        },
+        // 大きすぎる値で終了しないよう、失敗を返す`try_reserve`を使う
+        Command::Reserve(n) => {
+            if let Err(e) = vec.try_reserve(n) {
+                writeln!(out, "error: {}", e)?;
+            }
+        }
+        Command::Shrink => vec.shrink_to_fit(),
+        Command::Iter => {
+            let items: Vec<String> = vec.iter().map(|n| n.to_string()).collect();
+            writeln!(out, "[{}]", items.join(", "))?;
+        }
+        Command::Dump => {
+            for i in 0..vec.capacity() {
+                match vec.get(i) {
+                    Some(n) => writeln!(out, "  {:>3}: {}", i, n)?,
+                    None => writeln!(out, "  {:>3}: (spare)", i)?,
+                }
+            }
+        }
+        Command::Help => writeln!(out, "{}", HELP)?,
+        Command::Quit => return Ok(false),
+    }
+    print_state(vec, out)?;
+    Ok(true)
+}
+
+// 長さとキャパシティ、領域の図を書く
+fn print_state<W: Write>(vec: &ToyVec<i64>, out: &mut W) -> io::Result<()> {
+    let shown = vec.capacity().min(MAX_DIAGRAM_SLOTS);
+    let diagram: String = (0..shown)
+        .map(|i| if i < vec.len() { '#' } else { '.' })
+        .collect();
+    write!(
+        out,
+        "len: {}, capacity: {} [{}]",
+        vec.len(),
+        vec.capacity(),
+        diagram
+    )?;
+    if vec.capacity() > shown {
+        write!(out, " ({} more)", vec.capacity() - shown)?;
+    }
+    writeln!(out)
+}
+
+// `input`から読んだコマンドを順に実行する
+// `echo`が`true`ならコマンドを表示し、`false`なら対話用のプロンプトを表示する
+fn run<R: BufRead, W: Write>(input: R, out: &mut W, echo: bool) -> io::Result<()> {
+    let mut vec = ToyVec::new();
+    if !echo {
+        write!(out, "> ")?;
+        out.flush()?;
+    }
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim();
+        if echo {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            writeln!(out, "> {}", line)?;
+        }
+        if !line.is_empty() {
+            let keep_going = match parse(line) {
+                Ok(command) => execute(&mut vec, command, out)?,
+                Err(message) => {
+                    writeln!(out, "error: {}", message)?;
+                    true
+                }
+            };
+            if !keep_going {
+                return Ok(());
+            }
+        }
+        if !echo {
+            write!(out, "> ")?;
+            out.flush()?;
+        }
+    }
+    if !echo {
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let result = match args.as_slice() {
+        [] => run(io::stdin().lock(), &mut out, false),
+        [flag, path] if flag == "--script" => match File::open(path) {
+            Ok(file) => run(BufReader::new(file), &mut out, true),
+            Err(e) => {
+                eprintln!("cannot open {}: {}", path, e);
+                process::exit(1);
+            }
+        },
+        _ => {
+            eprintln!("usage: toy_vec_repl [--script <file>]");
+            process::exit(2);
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,259 @@
+// 最大の長さを超えて伸びない`ToyVec`
+// 満杯のときに要素を追加しようとした場合の扱いを`OverflowPolicy`で選ぶ
+use crate::{ToyVec, ToyVecError, TryPushError};
+use std::fmt;
+use std::mem;
+
+// 満杯のときに追加された要素の扱い
+pub enum OverflowPolicy<T> {
+    // 追加を拒み、要素を`Err`で呼び出し側へ返す
+    Reject,
+    // 最も古い要素を捨てて追加する（リングバッファ）
+    OverwriteOldest,
+    // 追加しようとした要素を黙って捨てる
+    DropNewest,
+    // 追加しようとした要素をコールバックに渡す
+    Callback(Box<dyn FnMut(T)>),
+}
+
+impl<T> fmt::Debug for OverflowPolicy<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OverflowPolicy::Reject => write!(f, "Reject"),
+            OverflowPolicy::OverwriteOldest => write!(f, "OverwriteOldest"),
+            OverflowPolicy::DropNewest => write!(f, "DropNewest"),
+            OverflowPolicy::Callback(_) => write!(f, "Callback(..)"),
+        }
+    }
+}
+
+// 満杯のために格納しなかった要素の数（ポリシーごと）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OverflowStats {
+    pub rejected: usize,
+    pub overwritten: usize,
+    pub dropped: usize,
+    pub handed_to_callback: usize,
+}
+
+impl OverflowStats {
+    // すべてのポリシーで捨てた要素の合計
+    pub fn total(&self) -> usize {
+        self.rejected + self.overwritten + self.dropped + self.handed_to_callback
+    }
+}
+
+pub struct BoundedToyVec<T> {
+    // 要素はリングバッファとして格納する。`head`が最も古い要素の位置
+    elements: Box<[T]>,
+    head: usize,
+    len: usize,
+    policy: OverflowPolicy<T>,
+    stats: OverflowStats,
+}
+
+impl<T: Default> BoundedToyVec<T> {
+    // 最大で`max_len`個の要素を格納する`BoundedToyVec`を作る。領域は最初にまとめて確保する
+    pub fn new(max_len: usize, policy: OverflowPolicy<T>) -> Self {
+        assert!(max_len > 0, "max_len should be greater than 0");
+        Self {
+            elements: ToyVec::allocate_in_heap(max_len),
+            head: 0,
+            len: 0,
+            policy,
+            stats: OverflowStats::default(),
+        }
+    }
+
+    pub fn max_len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == self.max_len()
+    }
+
+    pub fn policy(&self) -> &OverflowPolicy<T> {
+        &self.policy
+    }
+
+    // ポリシーを差し替えて、元のポリシーを返す。カウンタはそのまま引き継ぐ
+    pub fn set_policy(&mut self, policy: OverflowPolicy<T>) -> OverflowPolicy<T> {
+        mem::replace(&mut self.policy, policy)
+    }
+
+    pub fn stats(&self) -> OverflowStats {
+        self.stats
+    }
+
+    // 末尾に要素を追加する
+    // 満杯の場合はポリシーに従い、`Reject`のときだけ要素を`Err`で返す
+    pub fn push(&mut self, element: T) -> Result<(), T> {
+        if !self.is_full() {
+            let tail = self.physical(self.len);
+            self.elements[tail] = element;
+            self.len += 1;
+            return Ok(());
+        }
+        match &mut self.policy {
+            OverflowPolicy::Reject => {
+                self.stats.rejected += 1;
+                return Err(element);
+            }
+            OverflowPolicy::OverwriteOldest => {
+                // 満杯なので、最も古い要素の位置が新しい要素の位置になる
+                self.elements[self.head] = element;
+                self.head = self.physical(1);
+                self.stats.overwritten += 1;
+            }
+            OverflowPolicy::DropNewest => self.stats.dropped += 1,
+            OverflowPolicy::Callback(callback) => {
+                callback(element);
+                self.stats.handed_to_callback += 1;
+            }
+        }
+        Ok(())
+    }
+
+    // ポリシーにかかわらず、満杯なら追加せずに要素と`ToyVecError::Full`を返す（拒んだ数として数える）
+    pub fn try_push(&mut self, element: T) -> Result<(), TryPushError<T>> {
+        if self.is_full() {
+            self.stats.rejected += 1;
+            return Err(TryPushError::new(element, ToyVecError::Full));
+        }
+        self.push(element)
+            .map_err(|element| TryPushError::new(element, ToyVecError::Full))
+    }
+
+    // 最も新しい要素を取り除いて返す
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        let tail = self.physical(self.len);
+        Some(mem::take(&mut self.elements[tail]))
+    }
+
+    // 最も古い要素を取り除いて返す
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        let element = mem::take(&mut self.elements[self.head]);
+        self.head = self.physical(1);
+        self.len -= 1;
+        Some(element)
+    }
+
+    // 古いほうから数えて`index`番目の要素を返す
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index < self.len {
+            Some(&self.elements[self.physical(index)])
+        } else {
+            None
+        }
+    }
+
+    // 古い順に要素をたどるイテレータ
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        (0..self.len).map(move |i| &self.elements[self.physical(i)])
+    }
+
+    // 古い順に並べた`ToyVec`に変換する
+    pub fn into_toy_vec(mut self) -> ToyVec<T> {
+        let mut vec = ToyVec::with_capacity(self.len);
+        while let Some(element) = self.pop_front() {
+            vec.push(element);
+        }
+        vec
+    }
+
+    // 論理的な位置（古いほうからの順番）を`elements`の位置に変換する
+    fn physical(&self, index: usize) -> usize {
+        (self.head + index) % self.max_len()
+    }
+}
+
+impl<T: fmt::Debug + Default> fmt::Debug for BoundedToyVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BoundedToyVec, OverflowPolicy, OverflowStats};
+    use crate::{ToyVecError, TryPushError};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_reject() {
+        let mut v = BoundedToyVec::new(2, OverflowPolicy::Reject);
+        assert_eq!(v.push(1), Ok(()));
+        assert_eq!(v.push(2), Ok(()));
+        assert!(v.is_full());
+        assert_eq!(v.push(3), Err(3));
+        assert_eq!(v.pop(), Some(2));
+        assert_eq!(v.push(4), Ok(()));
+        assert_eq!(format!("{:?}", v), "[1, 4]");
+        assert_eq!(v.stats().rejected, 1);
+
+        // `try_push`はポリシーにかかわらず満杯ならエラーを返す
+        v.set_policy(OverflowPolicy::OverwriteOldest);
+        assert_eq!(v.try_push(5), Err(TryPushError::new(5, ToyVecError::Full)));
+        assert_eq!(v.stats().rejected, 2);
+    }
+
+    #[test]
+    fn test_overwrite_oldest() {
+        let mut v = BoundedToyVec::new(3, OverflowPolicy::OverwriteOldest);
+        for i in 0..10 {
+            v.push(i).unwrap();
+        }
+        assert_eq!(v.iter().copied().collect::<Vec<_>>(), [7, 8, 9]);
+        assert_eq!(v.get(0), Some(&7));
+        assert_eq!(v.get(3), None);
+        assert_eq!(v.pop_front(), Some(7));
+        v.push(10).unwrap();
+        assert_eq!(v.into_toy_vec().as_slice(), &[8, 9, 10]);
+    }
+
+    #[test]
+    fn test_drop_newest_and_callback() {
+        let mut v = BoundedToyVec::new(1, OverflowPolicy::DropNewest);
+        v.push("a").unwrap();
+        v.push("b").unwrap();
+
+        let spilled = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&spilled);
+        v.set_policy(OverflowPolicy::Callback(Box::new(move |s| {
+            sink.borrow_mut().push(s)
+        })));
+        v.push("c").unwrap();
+        v.push("d").unwrap();
+
+        assert_eq!(v.get(0), Some(&"a"));
+        assert_eq!(*spilled.borrow(), ["c", "d"]);
+        // ポリシーを切り替えても、それまでのカウンタは残る
+        assert_eq!(
+            v.stats(),
+            OverflowStats {
+                rejected: 0,
+                overwritten: 0,
+                dropped: 1,
+                handed_to_callback: 2,
+            }
+        );
+        assert_eq!(v.stats().total(), 3);
+    }
+}
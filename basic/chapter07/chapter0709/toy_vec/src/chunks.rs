@@ -0,0 +1,227 @@
+// `ToyVec`を区切ってたどるイテレータ
+use crate::{IntoIter, ToyVec};
+use std::convert::TryInto;
+use std::mem;
+
+impl<T: Default> ToyVec<T> {
+    // 要素を`size`個ずつの`ToyVec`に分けて返す。最後のチャンクは`size`個より少ないことがある
+    // 要素はムーブするので複製しない
+    pub fn into_chunks(self, size: usize) -> IntoChunks<T> {
+        assert!(size > 0, "chunk size should be greater than 0");
+        IntoChunks {
+            remaining: self.len,
+            iter: self.into_iter(),
+            size,
+        }
+    }
+
+    // 隣り合う要素の組に`pred`が`true`を返し続ける範囲を1つのスライスにまとめて返す
+    pub fn chunk_by<F>(&self, pred: F) -> ChunkBy<'_, T, F>
+    where
+        F: FnMut(&T, &T) -> bool,
+    {
+        ChunkBy {
+            rest: self.as_slice(),
+            pred,
+        }
+    }
+
+    // 連続する`N`個の要素を配列への参照として、1つずつずらしながら返す
+    pub fn array_windows<const N: usize>(&self) -> impl Iterator<Item = &[T; N]> {
+        assert!(N > 0, "window size should be greater than 0");
+        self.as_slice()
+            .windows(N)
+            .map(|window| window.try_into().unwrap())
+    }
+
+    // `pred`が`true`を返した要素を取り除き、イテレータとして返す。残った要素の順序は保たれる
+    // イテレータを最後までたどらずにドロップした場合、まだ調べていない要素はすべて残る
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, T, F>
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        ExtractIf {
+            vec: self,
+            read: 0,
+            write: 0,
+            pred,
+        }
+    }
+}
+
+pub struct IntoChunks<T> {
+    iter: IntoIter<T>,
+    remaining: usize,
+    size: usize,
+}
+
+impl<T: Default> Iterator for IntoChunks<T> {
+    type Item = ToyVec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let n = self.size.min(self.remaining);
+        self.remaining -= n;
+        let mut chunk = ToyVec::with_capacity(n);
+        for element in self.iter.by_ref().take(n) {
+            chunk.push(element);
+        }
+        Some(chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.remaining.div_ceil(self.size);
+        (n, Some(n))
+    }
+}
+
+pub struct ChunkBy<'vec, T, F> {
+    rest: &'vec [T],
+    pred: F,
+}
+
+impl<'vec, T, F> Iterator for ChunkBy<'vec, T, F>
+where
+    F: FnMut(&T, &T) -> bool,
+{
+    type Item = &'vec [T];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        let mut end = 1;
+        while end < self.rest.len() && (self.pred)(&self.rest[end - 1], &self.rest[end]) {
+            end += 1;
+        }
+        let (chunk, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        Some(chunk)
+    }
+}
+
+// `extract_if`が返すイテレータ
+//
+// `elements[..write]`が残すと決まった要素、`elements[read..len]`がまだ調べていない要素で、
+// その間の`elements[write..read]`は取り出した跡（`T::default()`が入っている）
+// 残す要素は前へ詰めながら進むので、全体でO(n)で済む。跡はドロップするときに末尾へ回す
+pub struct ExtractIf<'vec, T: Default, F> {
+    vec: &'vec mut ToyVec<T>,
+    read: usize,
+    write: usize,
+    pred: F,
+}
+
+impl<'vec, T: Default, F> Iterator for ExtractIf<'vec, T, F>
+where
+    F: FnMut(&mut T) -> bool,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.read < self.vec.len {
+            let i = self.read;
+            // `pred`がパニックしても`i`番目の要素が残るように、`read`は`pred`が返ってから進める
+            let extract = (self.pred)(&mut self.vec.elements[i]);
+            self.read += 1;
+            if extract {
+                return Some(mem::take(&mut self.vec.elements[i]));
+            }
+            self.vec.elements.swap(self.write, i);
+            self.write += 1;
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.vec.len - self.read))
+    }
+}
+
+impl<'vec, T: Default, F> Drop for ExtractIf<'vec, T, F> {
+    fn drop(&mut self) {
+        let gap = self.read - self.write;
+        if gap > 0 {
+            self.vec.elements[self.write..self.vec.len].rotate_left(gap);
+            self.vec.len -= gap;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ToyVec;
+    use std::panic::{self, AssertUnwindSafe};
+
+    fn toy_vec(items: &[i32]) -> ToyVec<i32> {
+        let mut v = ToyVec::new();
+        for &i in items {
+            v.push(i);
+        }
+        v
+    }
+
+    #[test]
+    fn test_into_chunks() {
+        let mut v = ToyVec::new();
+        for i in 0..7 {
+            v.push(i.to_string());
+        }
+        let chunks = v.into_chunks(3);
+        assert_eq!(chunks.size_hint(), (3, Some(3)));
+        let chunks: Vec<_> = chunks.collect();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].as_slice(), ["0", "1", "2"]);
+        assert_eq!(chunks[2].as_slice(), ["6"]);
+        assert_eq!(chunks[2].capacity(), 1);
+        assert_eq!(ToyVec::<i32>::new().into_chunks(2).count(), 0);
+    }
+
+    #[test]
+    fn test_chunk_by_and_array_windows() {
+        let v = toy_vec(&[1, 1, 2, 3, 3, 3, 1]);
+        let runs: Vec<&[i32]> = v.chunk_by(|a, b| a == b).collect();
+        assert_eq!(runs, [&[1, 1][..], &[2], &[3, 3, 3], &[1]]);
+        let ascending: Vec<&[i32]> = v.chunk_by(|a, b| a <= b).collect();
+        assert_eq!(ascending, [&[1, 1, 2, 3, 3, 3][..], &[1]]);
+
+        let diffs: Vec<i32> = v.array_windows().map(|[a, b]| b - a).collect();
+        assert_eq!(diffs, [0, 1, 1, 0, 0, -2]);
+        assert_eq!(v.array_windows::<8>().count(), 0);
+    }
+
+    #[test]
+    fn test_extract_if() {
+        let mut v = toy_vec(&(0..10).collect::<Vec<_>>());
+        let evens: Vec<i32> = v.extract_if(|x| *x % 2 == 0).collect();
+        assert_eq!(evens, [0, 2, 4, 6, 8]);
+        assert_eq!(v.as_slice(), &[1, 3, 5, 7, 9]);
+
+        // 途中でドロップしても、調べていない要素は順序を保って残る
+        {
+            let mut iter = v.extract_if(|x| {
+                *x *= 10;
+                *x > 20
+            });
+            assert_eq!(iter.next(), Some(30));
+        }
+        assert_eq!(v.as_slice(), &[10, 5, 7, 9]);
+    }
+
+    #[test]
+    fn test_extract_if_panic_keeps_elements() {
+        let mut v = toy_vec(&[1, 2, 3, 4, 5]);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            v.extract_if(|x| {
+                assert!(*x != 4, "unexpected element");
+                *x % 2 == 0
+            })
+            .count()
+        }));
+        assert!(result.is_err());
+        // 取り出した2だけがなくなり、パニックしたときに調べていた4とその後ろは残る
+        assert_eq!(v.as_slice(), &[1, 3, 4, 5]);
+    }
+}
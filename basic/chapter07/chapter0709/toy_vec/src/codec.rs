@@ -0,0 +1,771 @@
+// `ToyVec`とその仲間のコレクションのためのバイナリ形式
+//
+// 全体の形式:
+//   [バージョン(1バイト)] [値]
+// コレクションの形式:
+//   [要素数(varint)] [モード(1バイト)] [本体]
+//   モード0: 各要素をそのまま並べる
+//   モード1: ランレングス符号化。[繰り返し回数(varint)] [要素] の組を並べる
+//     `to_bytes_rle`でも、モード0より短くなるときだけ使う
+// 整数は可変長（LEB128）で書き、符号付き整数はジグザグ符号化してから書く
+//
+// 内部の状態を書く必要があるコレクション（`Grid`、`SparseToyVec`、スロットマップ）の実装は
+// それぞれのモジュールにある
+use crate::{PersistentToyVec, ToyInterner, ToyVec};
+use std::error::Error;
+use std::fmt;
+
+// 形式のバージョン。互換性のない変更を加えたら上げる
+pub const FORMAT_VERSION: u8 = 1;
+// デコードで受け付けるコレクションの要素数のデフォルトの上限
+pub const DEFAULT_MAX_LEN: usize = 1 << 24;
+// 1回のデコードで作る要素と文字列のバイトの合計のデフォルトの上限
+pub const DEFAULT_MAX_TOTAL: usize = 1 << 26;
+// 要素数の表示を信じて先に確保しておく要素数の上限
+// 悪意のある入力が巨大な要素数を書いていても、確保するのはこの数までで済む
+// （`serde_impl`でも同じ値を使う）
+pub(crate) const MAX_PREALLOC: usize = 4096;
+
+const MODE_PLAIN: u8 = 0;
+const MODE_RLE: u8 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    // 入力が途中で終わっている
+    UnexpectedEof,
+    UnsupportedVersion(u8),
+    // コレクションの要素数が上限を超えている
+    LengthLimitExceeded { len: u64, limit: usize },
+    // デコードで作る要素とバイトの合計が上限を超えた
+    // RLEの繰り返しを入れ子にすると、短い入力からいくらでも大きな値を作れるので、全体でも制限する
+    TotalLimitExceeded { limit: usize },
+    // 10バイトを超える、または`u64`に収まらないvarint
+    VarintOverflow,
+    // 値がデコード先の型の範囲に収まらない
+    IntegerOverflow,
+    // 未知のモードや`bool`でない値など、想定していないタグ
+    InvalidTag(u8),
+    InvalidUtf8,
+    InvalidChar(u32),
+    // グリッドの大きさとセルの数が合わないなど、値どうしが矛盾している
+    Inconsistent(&'static str),
+    // 値を読み終えた後に余分なバイトが残っている
+    TrailingBytes(usize),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::UnexpectedEof => write!(f, "unexpected end of input"),
+            DecodeError::UnsupportedVersion(v) => {
+                write!(
+                    f,
+                    "unsupported format version {} (expected {})",
+                    v, FORMAT_VERSION
+                )
+            }
+            DecodeError::LengthLimitExceeded { len, limit } => {
+                write!(
+                    f,
+                    "collection length {} exceeds the limit of {}",
+                    len, limit
+                )
+            }
+            DecodeError::TotalLimitExceeded { limit } => {
+                write!(f, "decoded data exceeds the total limit of {}", limit)
+            }
+            DecodeError::VarintOverflow => write!(f, "varint is too long"),
+            DecodeError::IntegerOverflow => write!(f, "integer out of range for the target type"),
+            DecodeError::InvalidTag(tag) => write!(f, "invalid tag byte {:#04x}", tag),
+            DecodeError::InvalidUtf8 => write!(f, "string is not valid UTF-8"),
+            DecodeError::InvalidChar(c) => write!(f, "{:#x} is not a valid char", c),
+            DecodeError::Inconsistent(reason) => write!(f, "inconsistent data: {}", reason),
+            DecodeError::TrailingBytes(n) => write!(f, "{} trailing bytes after the value", n),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+// バイナリ形式で書き出せる型
+pub trait Encode {
+    fn encode(&self, enc: &mut Encoder);
+}
+
+// バイナリ形式から読み込める型
+pub trait Decode: Sized {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DecodeError>;
+}
+
+// 値をバージョン付きのバイト列に変換する
+pub fn to_bytes<T: Encode + ?Sized>(value: &T) -> ToyVec<u8> {
+    let mut enc = Encoder::new();
+    value.encode(&mut enc);
+    enc.finish()
+}
+
+// `to_bytes`と同じだが、コレクションを（短くなる場合は）ランレングス符号化する
+pub fn to_bytes_rle<T: Encode + ?Sized>(value: &T) -> ToyVec<u8> {
+    let mut enc = Encoder::with_rle();
+    value.encode(&mut enc);
+    enc.finish()
+}
+
+// `to_bytes`で作ったバイト列を値に戻す。コレクションの要素数は`DEFAULT_MAX_LEN`までに制限する
+pub fn from_bytes<T: Decode>(bytes: &[u8]) -> Result<T, DecodeError> {
+    Decoder::new(bytes).finish()
+}
+
+pub struct Encoder {
+    buf: ToyVec<u8>,
+    rle: bool,
+}
+
+impl Encoder {
+    // バージョンのバイトを書いたエンコーダを作る
+    pub fn new() -> Self {
+        let mut buf = ToyVec::new();
+        buf.push(FORMAT_VERSION);
+        Self { buf, rle: false }
+    }
+
+    pub fn with_rle() -> Self {
+        Self {
+            rle: true,
+            ..Self::new()
+        }
+    }
+
+    pub fn write_u8(&mut self, byte: u8) {
+        self.buf.push(byte);
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.buf.push(b);
+        }
+    }
+
+    // 下位7ビットずつ、続きがあれば最上位ビットを立てて書く
+    pub fn write_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.write_u8((value as u8 & 0x7f) | 0x80);
+            value >>= 7;
+        }
+        self.write_u8(value as u8);
+    }
+
+    // `len`個の要素を書く。RLEが有効なら、各要素を書き出したバイト列を比較して連続を数え、
+    // ランレングス符号化した方が短くなる場合だけモード1で書く
+    pub fn write_seq<'a, T, I>(&mut self, len: usize, items: I)
+    where
+        T: Encode + 'a,
+        I: IntoIterator<Item = &'a T>,
+    {
+        self.write_varint(len as u64);
+        if !self.rle {
+            self.write_u8(MODE_PLAIN);
+            for item in items {
+                item.encode(self);
+            }
+            return;
+        }
+
+        // 要素を書き出したバイト列と、その繰り返し回数
+        let mut runs: Vec<(ToyVec<u8>, u64)> = Vec::new();
+        for item in items {
+            let mut scratch = Encoder {
+                buf: ToyVec::new(),
+                rle: true,
+            };
+            item.encode(&mut scratch);
+            match runs.last_mut() {
+                Some((bytes, count)) if *bytes == scratch.buf => *count += 1,
+                _ => runs.push((scratch.buf, 1)),
+            }
+        }
+        let plain_len: u64 = runs
+            .iter()
+            .map(|(bytes, count)| bytes.len() as u64 * count)
+            .sum();
+        let rle_len: u64 = runs
+            .iter()
+            .map(|(bytes, count)| varint_len(*count) + bytes.len() as u64)
+            .sum();
+        if rle_len < plain_len {
+            self.write_u8(MODE_RLE);
+            for (bytes, count) in &runs {
+                self.write_varint(*count);
+                self.write_bytes(bytes.as_slice());
+            }
+        } else {
+            self.write_u8(MODE_PLAIN);
+            for (bytes, count) in &runs {
+                for _ in 0..*count {
+                    self.write_bytes(bytes.as_slice());
+                }
+            }
+        }
+    }
+
+    pub fn finish(self) -> ToyVec<u8> {
+        self.buf
+    }
+}
+
+// `write_varint`で書いたときのバイト数
+fn varint_len(value: u64) -> u64 {
+    let bits = 64 - u64::from(value.leading_zeros());
+    bits.max(1).div_ceil(7)
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Decoder<'a> {
+    input: &'a [u8],
+    pos: usize,
+    max_len: usize,
+    // まだ作ってよい要素とバイトの数。入れ子のコレクションも含めて共有する
+    budget: usize,
+    max_total: usize,
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(input: &'a [u8]) -> Self {
+        Self::with_max_len(input, DEFAULT_MAX_LEN)
+    }
+
+    // コレクションの要素数の上限を指定してデコーダを作る
+    pub fn with_max_len(input: &'a [u8], max_len: usize) -> Self {
+        Self::with_limits(input, max_len, DEFAULT_MAX_TOTAL)
+    }
+
+    // コレクションごとの要素数の上限と、全体で作る要素とバイトの合計の上限を指定してデコーダを作る
+    pub fn with_limits(input: &'a [u8], max_len: usize, max_total: usize) -> Self {
+        Self {
+            input,
+            pos: 0,
+            max_len,
+            budget: max_total,
+            max_total,
+        }
+    }
+
+    // バージョンを確かめてから値を1つ読み、入力を読み切ったことを確かめる
+    pub fn finish<T: Decode>(mut self) -> Result<T, DecodeError> {
+        let version = self.read_u8()?;
+        if version != FORMAT_VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+        let value = T::decode(&mut self)?;
+        match self.remaining() {
+            0 => Ok(value),
+            n => Err(DecodeError::TrailingBytes(n)),
+        }
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.input.len() - self.pos
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, DecodeError> {
+        let byte = *self.input.get(self.pos).ok_or(DecodeError::UnexpectedEof)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    pub fn read_bytes(&mut self, n: usize) -> Result<&'a [u8], DecodeError> {
+        if n > self.remaining() {
+            return Err(DecodeError::UnexpectedEof);
+        }
+        let bytes = &self.input[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    pub fn read_varint(&mut self) -> Result<u64, DecodeError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_u8()?;
+            let bits = u64::from(byte & 0x7f);
+            // 10バイト目は1ビットしか使えない
+            if shift == 63 && bits > 1 {
+                return Err(DecodeError::VarintOverflow);
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(DecodeError::VarintOverflow)
+    }
+
+    // コレクションの要素数を読み、上限を超えていないか確かめる
+    pub fn read_len(&mut self) -> Result<usize, DecodeError> {
+        let len = self.read_varint()?;
+        if len > self.max_len as u64 {
+            return Err(DecodeError::LengthLimitExceeded {
+                len,
+                limit: self.max_len,
+            });
+        }
+        Ok(len as usize)
+    }
+
+    // これから`n`個の要素（またはバイト）を作ることを申告する。全体の上限を超えるならエラーを返す
+    // 独自の`Decode`実装で、入力の長さに比例しない量の値を作る場合にも使う
+    pub fn charge(&mut self, n: usize) -> Result<(), DecodeError> {
+        match self.budget.checked_sub(n) {
+            Some(budget) => {
+                self.budget = budget;
+                Ok(())
+            }
+            None => Err(DecodeError::TotalLimitExceeded {
+                limit: self.max_total,
+            }),
+        }
+    }
+
+    // `write_seq`で書いた要素を読む
+    pub fn read_seq<T: Decode + Default>(&mut self) -> Result<ToyVec<T>, DecodeError> {
+        let len = self.read_len()?;
+        let mode = self.read_u8()?;
+        // 要素数の表示は信用せず、先に確保するのは一部だけにする
+        let mut items = ToyVec::with_capacity(len.min(MAX_PREALLOC));
+        match mode {
+            MODE_PLAIN => {
+                // どの要素も1バイト以上あるので、残りのバイト数より多いことはありえない
+                if len > self.remaining() {
+                    return Err(DecodeError::UnexpectedEof);
+                }
+                for _ in 0..len {
+                    self.charge(1)?;
+                    items.push(T::decode(self)?);
+                }
+            }
+            MODE_RLE => {
+                while items.len() < len {
+                    let count = self.read_varint()?;
+                    // 繰り返し回数も、宣言された要素数を超えないか確かめる
+                    if count == 0 || count > (len - items.len()) as u64 {
+                        return Err(DecodeError::LengthLimitExceeded {
+                            len: count,
+                            limit: len - items.len(),
+                        });
+                    }
+                    // 繰り返しを展開する前に、作る要素の数を全体の上限から差し引いておく
+                    self.charge(count as usize)?;
+                    let start = self.pos;
+                    for _ in 0..count {
+                        // 同じバイト列を繰り返し読むことで、`T: Clone`を要求せずに済ませる
+                        self.pos = start;
+                        items.push(T::decode(self)?);
+                    }
+                }
+            }
+            tag => return Err(DecodeError::InvalidTag(tag)),
+        }
+        Ok(items)
+    }
+}
+
+//
+// 基本的な型の実装
+//
+
+impl Encode for u8 {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.write_u8(*self);
+    }
+}
+
+impl Decode for u8 {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        dec.read_u8()
+    }
+}
+
+impl Encode for bool {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.write_u8(*self as u8);
+    }
+}
+
+impl Decode for bool {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        match dec.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            tag => Err(DecodeError::InvalidTag(tag)),
+        }
+    }
+}
+
+// 符号なし整数はそのままvarintで書く
+macro_rules! impl_unsigned {
+    ($($t:ty),*) => {
+        $(
+            impl Encode for $t {
+                fn encode(&self, enc: &mut Encoder) {
+                    enc.write_varint(*self as u64);
+                }
+            }
+
+            impl Decode for $t {
+                fn decode(dec: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+                    let value = dec.read_varint()?;
+                    <$t as std::convert::TryFrom<u64>>::try_from(value)
+                        .map_err(|_| DecodeError::IntegerOverflow)
+                }
+            }
+        )*
+    };
+}
+
+impl_unsigned!(u16, u32, u64, usize);
+
+// 符号付き整数は、絶対値の小さい負の数も短く書けるようジグザグ符号化する（0, -1, 1, -2, ... → 0, 1, 2, 3, ...）
+macro_rules! impl_signed {
+    ($($t:ty),*) => {
+        $(
+            impl Encode for $t {
+                fn encode(&self, enc: &mut Encoder) {
+                    let v = *self as i64;
+                    enc.write_varint(((v << 1) ^ (v >> 63)) as u64);
+                }
+            }
+
+            impl Decode for $t {
+                fn decode(dec: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+                    let z = dec.read_varint()?;
+                    let value = ((z >> 1) as i64) ^ -((z & 1) as i64);
+                    <$t as std::convert::TryFrom<i64>>::try_from(value)
+                        .map_err(|_| DecodeError::IntegerOverflow)
+                }
+            }
+        )*
+    };
+}
+
+impl_signed!(i8, i16, i32, i64, isize);
+
+impl Encode for f32 {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.write_bytes(&self.to_le_bytes());
+    }
+}
+
+impl Decode for f32 {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(dec.read_bytes(4)?);
+        Ok(f32::from_le_bytes(bytes))
+    }
+}
+
+impl Encode for f64 {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.write_bytes(&self.to_le_bytes());
+    }
+}
+
+impl Decode for f64 {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(dec.read_bytes(8)?);
+        Ok(f64::from_le_bytes(bytes))
+    }
+}
+
+impl Encode for char {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.write_varint(u64::from(*self as u32));
+    }
+}
+
+impl Decode for char {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let value = u32::decode(dec)?;
+        std::char::from_u32(value).ok_or(DecodeError::InvalidChar(value))
+    }
+}
+
+impl Encode for str {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.write_varint(self.len() as u64);
+        enc.write_bytes(self.as_bytes());
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, enc: &mut Encoder) {
+        self.as_str().encode(enc);
+    }
+}
+
+impl Decode for String {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        // `read_bytes`は入力の残りより長い長さを受け付けないので、長さの表示で巨大な確保は起きない
+        let len = usize::decode(dec)?;
+        let bytes = dec.read_bytes(len)?;
+        // RLEで同じ文字列を繰り返し読む場合に備え、バイト数も全体の上限から差し引く
+        dec.charge(len)?;
+        std::str::from_utf8(bytes)
+            .map(str::to_string)
+            .map_err(|_| DecodeError::InvalidUtf8)
+    }
+}
+
+// `None`はタグ0、`Some`はタグ1の後に値を書く
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            None => enc.write_u8(0),
+            Some(value) => {
+                enc.write_u8(1);
+                value.encode(enc);
+            }
+        }
+    }
+}
+
+impl<T: Decode> Decode for Option<T> {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        match dec.read_u8()? {
+            0 => Ok(None),
+            1 => Ok(Some(T::decode(dec)?)),
+            tag => Err(DecodeError::InvalidTag(tag)),
+        }
+    }
+}
+
+//
+// コレクションの実装
+//
+
+impl<T: Encode + Default> Encode for ToyVec<T> {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.write_seq(self.len(), self.iter());
+    }
+}
+
+impl<T: Decode + Default> Decode for ToyVec<T> {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        dec.read_seq()
+    }
+}
+
+impl<T: Encode + Clone + Default> Encode for PersistentToyVec<T> {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.write_seq(self.len(), self.iter());
+    }
+}
+
+impl<T: Decode + Clone + Default> Decode for PersistentToyVec<T> {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        Ok(dec.read_seq::<T>()?.into_iter().collect())
+    }
+}
+
+// インターナは文字列をインターンした順に書く。読み込み時に同じ順でインターンし直すので、
+// シンボルも元と同じになる
+impl Encode for ToyInterner {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.write_varint(self.len() as u64);
+        enc.write_u8(MODE_PLAIN);
+        for (_, s) in self.iter() {
+            s.encode(enc);
+        }
+    }
+}
+
+impl Decode for ToyInterner {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let strings = dec.read_seq::<String>()?;
+        let mut interner = ToyInterner::with_capacity(strings.len());
+        for s in strings.iter() {
+            interner.intern(s);
+        }
+        Ok(interner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_bytes, to_bytes, to_bytes_rle, DecodeError, Decoder, Encode, Encoder};
+    use crate::{PersistentToyVec, ToyInterner, ToyVec};
+
+    fn toy_vec<T: Default>(items: Vec<T>) -> ToyVec<T> {
+        let mut v = ToyVec::new();
+        for item in items {
+            v.push(item);
+        }
+        v
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let v = toy_vec(vec![0i64, -1, 1, i64::MIN, i64::MAX]);
+        assert_eq!(from_bytes::<ToyVec<i64>>(to_bytes(&v).as_slice()), Ok(v));
+
+        let v = toy_vec(vec!["Java Finch".to_string(), "ブンチョウ".to_string()]);
+        assert_eq!(from_bytes::<ToyVec<String>>(to_bytes(&v).as_slice()), Ok(v));
+
+        let v = toy_vec(vec![toy_vec(vec![1.5f64]), ToyVec::new()]);
+        assert_eq!(
+            from_bytes::<ToyVec<ToyVec<f64>>>(to_bytes(&v).as_slice()),
+            Ok(v)
+        );
+
+        let p: PersistentToyVec<u32> = (0..100).collect();
+        assert_eq!(
+            from_bytes::<PersistentToyVec<u32>>(to_bytes(&p).as_slice()),
+            Ok(p)
+        );
+
+        let mut interner = ToyInterner::new();
+        let dutch = interner.intern("ダッチ");
+        interner.intern("ロップイヤー");
+        let decoded = from_bytes::<ToyInterner>(to_bytes(&interner).as_slice()).unwrap();
+        assert_eq!(decoded.resolve(dutch), Some("ダッチ"));
+        assert_eq!(decoded.len(), 2);
+    }
+
+    #[test]
+    fn test_compact_encoding() {
+        // 小さい整数は1バイトで書ける
+        let v = toy_vec(vec![1u64, 2, 3]);
+        assert_eq!(to_bytes(&v).as_slice(), &[1, 3, 0, 1, 2, 3]);
+        let v = toy_vec(vec![-1i32, 300]);
+        assert_eq!(to_bytes(&v).as_slice(), &[1, 2, 0, 1, 0xd8, 0x04]);
+
+        // 同じ値が続けばRLEの方が短くなる
+        let v = toy_vec(vec![7u32; 1000]);
+        let rle = to_bytes_rle(&v);
+        assert_eq!(rle.as_slice(), &[1, 0xe8, 0x07, 1, 0xe8, 0x07, 7]);
+        assert_eq!(from_bytes::<ToyVec<u32>>(rle.as_slice()), Ok(v));
+
+        let v = toy_vec(vec!["a".to_string(), "a".to_string(), "b".to_string()]);
+        assert_eq!(
+            from_bytes::<ToyVec<String>>(to_bytes_rle(&v).as_slice()),
+            Ok(v)
+        );
+
+        // 繰り返しがなければ、RLEを指定しても元の形式より長くならない
+        let v = toy_vec((0..200u32).collect());
+        let rle = to_bytes_rle(&v);
+        assert!(rle.len() <= to_bytes(&v).len());
+        assert_eq!(from_bytes::<ToyVec<u32>>(rle.as_slice()), Ok(v));
+        let v = toy_vec(vec![toy_vec(vec![1u8, 2]), toy_vec(vec![0; 50])]);
+        let rle = to_bytes_rle(&v);
+        assert!(rle.len() < to_bytes(&v).len());
+        assert_eq!(from_bytes::<ToyVec<ToyVec<u8>>>(rle.as_slice()), Ok(v));
+    }
+
+    #[test]
+    fn test_reject_malformed_input() {
+        // バージョンが違う
+        assert_eq!(
+            from_bytes::<ToyVec<u8>>(&[9, 0, 0]),
+            Err(DecodeError::UnsupportedVersion(9))
+        );
+        // 要素数が上限を超える
+        let mut enc = Encoder::new();
+        enc.write_varint(u64::MAX);
+        enc.write_u8(0);
+        assert_eq!(
+            from_bytes::<ToyVec<u8>>(enc.finish().as_slice()),
+            Err(DecodeError::LengthLimitExceeded {
+                len: u64::MAX,
+                limit: super::DEFAULT_MAX_LEN
+            })
+        );
+        // 上限以内でも、入力より多い要素数は確保する前に弾く
+        assert_eq!(
+            from_bytes::<ToyVec<u64>>(&[1, 0xff, 0xff, 0x3f, 0]),
+            Err(DecodeError::UnexpectedEof)
+        );
+        // RLEでも上限は守られる
+        let bomb = [1, 0xff, 0xff, 0xff, 0x7f, 1, 0xff, 0xff, 0xff, 0x7f, 0];
+        let dec = Decoder::with_max_len(&bomb, 1000);
+        assert!(matches!(
+            dec.finish::<ToyVec<u8>>(),
+            Err(DecodeError::LengthLimitExceeded { .. })
+        ));
+
+        assert_eq!(
+            from_bytes::<ToyVec<u8>>(&[1, 1, 2, 0]),
+            Err(DecodeError::InvalidTag(2))
+        );
+        assert_eq!(
+            from_bytes::<Option<u8>>(&[1, 2, 0]),
+            Err(DecodeError::InvalidTag(2))
+        );
+        assert_eq!(
+            from_bytes::<ToyVec<u8>>(&[1, 0, 0, 0xaa]),
+            Err(DecodeError::TrailingBytes(1))
+        );
+        assert_eq!(
+            from_bytes::<u16>(&[1, 0x80, 0x80, 0x04]),
+            Err(DecodeError::IntegerOverflow)
+        );
+        assert_eq!(
+            from_bytes::<u64>(&[1, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02]),
+            Err(DecodeError::VarintOverflow)
+        );
+        assert_eq!(
+            from_bytes::<String>(&[1, 2, 0xc3, 0x28]),
+            Err(DecodeError::InvalidUtf8)
+        );
+    }
+
+    // RLEで`count`個の`element`を並べたコレクションを書く
+    fn write_rle_run(enc: &mut Encoder, count: u64, element: impl FnOnce(&mut Encoder)) {
+        enc.write_varint(count);
+        enc.write_u8(1);
+        enc.write_varint(count);
+        element(enc);
+    }
+
+    #[test]
+    fn test_reject_rle_bomb() {
+        // 要素数は上限以内でも、全体の上限を超える要素は作らない
+        let mut enc = Encoder::new();
+        write_rle_run(&mut enc, 1000, |enc| enc.write_u8(0));
+        let bytes = enc.finish();
+        let dec = Decoder::with_limits(bytes.as_slice(), 1000, 100);
+        assert_eq!(
+            dec.finish::<ToyVec<u8>>(),
+            Err(DecodeError::TotalLimitExceeded { limit: 100 })
+        );
+        let dec = Decoder::with_limits(bytes.as_slice(), 1000, 1000);
+        assert_eq!(dec.finish::<ToyVec<u8>>().unwrap().len(), 1000);
+
+        // 入れ子にすると数バイトで1000 * 1000要素になるが、内側の要素も同じ上限に数える
+        let mut enc = Encoder::new();
+        write_rle_run(&mut enc, 1000, |enc| {
+            write_rle_run(enc, 1000, |enc| enc.write_u8(0))
+        });
+        let bytes = enc.finish();
+        assert!(bytes.len() < 16);
+        let dec = Decoder::with_limits(bytes.as_slice(), 1000, 100_000);
+        assert_eq!(
+            dec.finish::<ToyVec<ToyVec<u8>>>(),
+            Err(DecodeError::TotalLimitExceeded { limit: 100_000 })
+        );
+
+        // 長い文字列を繰り返す場合は、文字列のバイト数も数える
+        let mut enc = Encoder::new();
+        write_rle_run(&mut enc, 1000, |enc| "a".repeat(1000).encode(enc));
+        let bytes = enc.finish();
+        let dec = Decoder::with_limits(bytes.as_slice(), 1000, 100_000);
+        assert_eq!(
+            dec.finish::<ToyVec<String>>(),
+            Err(DecodeError::TotalLimitExceeded { limit: 100_000 })
+        );
+    }
+}
@@ -0,0 +1,564 @@
+// 長さとキャパシティを要素と同じヒープ領域の先頭（ヘッダ）に置く`ToyVec`
+// 構造体そのものはヘッダを指すポインタ1つだけなので、64ビット環境で8バイトに収まる
+// （`ToyVec`はBox<[T]>と長さで24バイト）。空のベクタを大量に持つ場合に向いている
+//
+// 領域のレイアウト:
+//   [Header { len: u32, cap: u32 }][パディング][T; cap]
+// 長さとキャパシティは`u32`なので、要素数は`u32::MAX`までに制限される
+// それを超える場合は`ToyVecError::CapacityOverflow`になる
+use crate::{ToyVecError, TryPushError};
+use std::alloc::{self, Layout};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ptr::{self, NonNull};
+use std::slice;
+
+#[repr(C)]
+struct Header {
+    len: u32,
+    cap: u32,
+}
+
+// キャパシティが0のベクタはすべてこのヘッダを指し、ヒープ領域を確保しない
+// 書き込むことはないので、共有しても問題ない
+static EMPTY_HEADER: Header = Header { len: 0, cap: 0 };
+
+pub struct CompactToyVec<T> {
+    ptr: NonNull<Header>,
+    // `T`を所有していることをコンパイラに伝える（ドロップチェックのため）
+    _marker: PhantomData<T>,
+}
+
+// 要素を所有しているだけなので、`Vec<T>`と同じ条件でスレッド間で送ったり共有したりできる
+unsafe impl<T: Send> Send for CompactToyVec<T> {}
+unsafe impl<T: Sync> Sync for CompactToyVec<T> {}
+
+impl<T> CompactToyVec<T> {
+    // キャパシティが0の`CompactToyVec`を作る。ヒープ領域は確保しない
+    pub fn new() -> Self {
+        Self {
+            ptr: NonNull::from(&EMPTY_HEADER),
+            _marker: PhantomData,
+        }
+    }
+
+    // 指定されたキャパシティを持つ`CompactToyVec`を作る
+    // キャパシティが`u32::MAX`を超えるとパニックする
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::try_with_capacity(capacity).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    // `with_capacity`のパニックしない版
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, ToyVecError> {
+        let mut vec = Self::new();
+        vec.try_reserve(capacity)?;
+        Ok(vec)
+    }
+
+    pub fn len(&self) -> usize {
+        self.header().len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.header().cap as usize
+    }
+
+    // 末尾に要素を追加する。要素数が`u32::MAX`を超えるとパニックする
+    pub fn push(&mut self, element: T) {
+        if let Err(e) = self.try_push(element) {
+            panic!("{}", e);
+        }
+    }
+
+    // `push`のパニックしない版。要素数が`u32::MAX`を超える場合は要素とエラーを返し、ベクタは変更しない
+    pub fn try_push(&mut self, element: T) -> Result<(), TryPushError<T>> {
+        if self.len() == self.capacity() {
+            if let Err(e) = self.try_grow() {
+                return Err(TryPushError::new(element, e));
+            }
+        }
+        let len = self.len();
+        unsafe {
+            self.data().add(len).write(element);
+            self.header_mut().len += 1;
+        }
+        Ok(())
+    }
+
+    // 少なくとも`additional`個の要素を追加できるようにキャパシティを増やす
+    // 要素数が`u32::MAX`を超えるとパニックする
+    pub fn reserve(&mut self, additional: usize) {
+        if let Err(e) = self.try_reserve(additional) {
+            panic!("{}", e);
+        }
+    }
+
+    // `reserve`のパニックしない版
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), ToyVecError> {
+        let required = self
+            .len()
+            .checked_add(additional)
+            .ok_or(ToyVecError::CapacityOverflow)?;
+        if required <= self.capacity() {
+            return Ok(());
+        }
+        if required > u32::MAX as usize {
+            return Err(ToyVecError::CapacityOverflow);
+        }
+        self.reallocate(required as u32)
+    }
+
+    // キャパシティを要素数まで縮める。要素がなければ領域を解放し、共有の空ヘッダを指すようにする
+    pub fn shrink_to_fit(&mut self) {
+        let len = self.len();
+        if self.capacity() == len {
+            return;
+        }
+        if len > 0 {
+            // 長さは`u32`に収まっている
+            if let Err(e) = self.reallocate(len as u32) {
+                panic!("{}", e);
+            }
+            return;
+        }
+        if let Ok((layout, _)) = Self::layout(self.capacity()) {
+            unsafe {
+                alloc::dealloc(self.ptr.as_ptr() as *mut u8, layout);
+            }
+        }
+        self.ptr = NonNull::from(&EMPTY_HEADER);
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.as_slice().get(index)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.as_mut_slice().get_mut(index)
+    }
+
+    // インデックスが範囲内なら要素への参照を返し、さもなければdefaultで与えた別の値への参照を返す
+    pub fn get_or<'a>(&'a self, index: usize, default: &'a T) -> &'a T {
+        self.get(index).unwrap_or(default)
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        unsafe {
+            self.header_mut().len -= 1;
+            // 長さを減らしたので、末尾の要素はもうベクタからは見えない。所有権を読み出して返す
+            Some(self.data().add(self.len()).read())
+        }
+    }
+
+    // `index`の位置に要素を挿入する。それ以降の要素は1つずつ後ろへずれる
+    pub fn insert(&mut self, index: usize, element: T) {
+        let len = self.len();
+        assert!(
+            index <= len,
+            "insertion index (is {}) should be <= len (is {})",
+            index,
+            len
+        );
+        if len == self.capacity() {
+            self.grow();
+        }
+        unsafe {
+            let p = self.data().add(index);
+            ptr::copy(p, p.add(1), len - index);
+            p.write(element);
+            self.header_mut().len += 1;
+        }
+    }
+
+    // `index`の要素を取り除いて返す。それ以降の要素は1つずつ前へずれる
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        let len = self.len();
+        if index >= len {
+            return None;
+        }
+        unsafe {
+            let p = self.data().add(index);
+            let element = p.read();
+            ptr::copy(p.add(1), p, len - index - 1);
+            self.header_mut().len -= 1;
+            Some(element)
+        }
+    }
+
+    // `index`の要素を取り除いて返す。空いた位置には最後の要素を移すので順序は保たれないが、O(1)で済む
+    pub fn swap_remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len() {
+            return None;
+        }
+        let last = self.len() - 1;
+        self.as_mut_slice().swap(index, last);
+        self.pop()
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.data(), self.len()) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.data(), self.len()) }
+    }
+
+    // 領域を2倍に拡張する。`u32::MAX`を超えるとパニックする
+    pub fn grow(&mut self) {
+        if let Err(e) = self.try_grow() {
+            panic!("{}", e);
+        }
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, T> {
+        self.as_mut_slice().iter_mut()
+    }
+
+    // キャパシティを2倍にする。最大でも`u32::MAX`までで、すでにそれに達していればエラーを返す
+    fn try_grow(&mut self) -> Result<(), ToyVecError> {
+        let cap = self.header().cap;
+        if cap == u32::MAX {
+            return Err(ToyVecError::CapacityOverflow);
+        }
+        let new_cap = cap.saturating_mul(2).max(1);
+        self.reallocate(new_cap)
+    }
+
+    // 領域を`new_cap`個の要素が入る大きさで確保し直す。ヘッダも一緒に移る
+    // 確保できなければ`ToyVecError::AllocFailed`を返す。そのとき元の領域はそのまま残る
+    fn reallocate(&mut self, new_cap: u32) -> Result<(), ToyVecError> {
+        let (new_layout, _) = Self::layout(new_cap as usize)?;
+        let ptr = unsafe {
+            if self.capacity() == 0 {
+                let ptr = alloc::alloc(new_layout) as *mut Header;
+                if !ptr.is_null() {
+                    ptr.write(Header { len: 0, cap: 0 });
+                }
+                ptr
+            } else {
+                let (old_layout, _) = Self::layout(self.capacity())?;
+                alloc::realloc(self.ptr.as_ptr() as *mut u8, old_layout, new_layout.size())
+                    as *mut Header
+            }
+        };
+        self.ptr = NonNull::new(ptr).ok_or(ToyVecError::AllocFailed)?;
+        unsafe {
+            self.header_mut().cap = new_cap;
+        }
+        Ok(())
+    }
+
+    // ヘッダと`cap`個の要素をまとめたレイアウトと、要素が始まる位置（バイト単位）を返す
+    fn layout(cap: usize) -> Result<(Layout, usize), ToyVecError> {
+        let elements = Layout::array::<T>(cap).map_err(|_| ToyVecError::CapacityOverflow)?;
+        let (layout, offset) = Layout::new::<Header>()
+            .extend(elements)
+            .map_err(|_| ToyVecError::CapacityOverflow)?;
+        Ok((layout.pad_to_align(), offset))
+    }
+
+    fn header(&self) -> &Header {
+        unsafe { self.ptr.as_ref() }
+    }
+
+    // 共有の空ヘッダには書き込まないこと（キャパシティが1以上のときだけ呼ぶ）
+    unsafe fn header_mut(&mut self) -> &mut Header {
+        self.ptr.as_mut()
+    }
+
+    // 先頭の要素へのポインタ。キャパシティが0ならダングリングポインタを返す
+    fn data(&self) -> *mut T {
+        if self.capacity() == 0 {
+            return NonNull::dangling().as_ptr();
+        }
+        // 要素の開始位置は`T`の型だけで決まり、キャパシティには依存しない
+        let offset = match Self::layout(0) {
+            Ok((_, offset)) => offset,
+            Err(_) => unreachable!(),
+        };
+        unsafe { (self.ptr.as_ptr() as *mut u8).add(offset) as *mut T }
+    }
+}
+
+impl<T> Drop for CompactToyVec<T> {
+    fn drop(&mut self) {
+        if self.capacity() == 0 {
+            return;
+        }
+        unsafe {
+            ptr::drop_in_place(self.as_mut_slice());
+            if let Ok((layout, _)) = Self::layout(self.capacity()) {
+                alloc::dealloc(self.ptr.as_ptr() as *mut u8, layout);
+            }
+        }
+    }
+}
+
+impl<T> Default for CompactToyVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> Clone for CompactToyVec<T> {
+    fn clone(&self) -> Self {
+        let mut cloned = Self::with_capacity(self.len());
+        for elem in self.iter() {
+            cloned.push(elem.clone());
+        }
+        cloned
+    }
+}
+
+impl<T: PartialEq> PartialEq for CompactToyVec<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for CompactToyVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.as_slice().fmt(f)
+    }
+}
+
+impl<T> std::iter::FromIterator<T> for CompactToyVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut vec = Self::new();
+        for elem in iter {
+            vec.push(elem);
+        }
+        vec
+    }
+}
+
+impl<'vec, T> IntoIterator for &'vec CompactToyVec<T> {
+    type Item = &'vec T;
+    type IntoIter = slice::Iter<'vec, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'vec, T> IntoIterator for &'vec mut CompactToyVec<T> {
+    type Item = &'vec mut T;
+    type IntoIter = slice::IterMut<'vec, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T> IntoIterator for CompactToyVec<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        let end = self.len();
+        // 要素の所有権はイテレータが持つので、ベクタ側の長さは0にしておく
+        if self.capacity() > 0 {
+            unsafe {
+                self.header_mut().len = 0;
+            }
+        }
+        IntoIter {
+            vec: self,
+            pos: 0,
+            end,
+        }
+    }
+}
+
+// 要素の所有権をとるイテレータ
+pub struct IntoIter<T> {
+    // 領域の解放だけを受け持つ（長さは0）
+    vec: CompactToyVec<T>,
+    pos: usize,
+    end: usize,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos == self.end {
+            return None;
+        }
+        let elem = unsafe { self.vec.data().add(self.pos).read() };
+        self.pos += 1;
+        Some(elem)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.end - self.pos;
+        (n, Some(n))
+    }
+}
+
+impl<T> Drop for IntoIter<T> {
+    fn drop(&mut self) {
+        // まだ取り出していない要素をドロップする。領域は`vec`のドロップで解放される
+        unsafe {
+            let rest = self.vec.data().add(self.pos);
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(rest, self.end - self.pos));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CompactToyVec;
+    use crate::{ToyVecError, TryPushError};
+    use std::cell::Cell;
+    use std::mem;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_size() {
+        assert_eq!(
+            mem::size_of::<CompactToyVec<u64>>(),
+            mem::size_of::<usize>()
+        );
+        // ポインタがNULLにならないので、`Option`で包んでも大きさは変わらない
+        assert_eq!(
+            mem::size_of::<Option<CompactToyVec<String>>>(),
+            mem::size_of::<usize>()
+        );
+    }
+
+    #[test]
+    fn test_basic_operations() {
+        let mut v = CompactToyVec::new();
+        assert_eq!(v.capacity(), 0);
+        for i in 0..100u64 {
+            v.push(i);
+        }
+        assert_eq!(v.len(), 100);
+        assert_eq!(v.capacity(), 128);
+        assert_eq!(v.get(10), Some(&10));
+        assert_eq!(v.get(100), None);
+        assert_eq!(v.get_or(100, &7), &7);
+
+        v.insert(0, 1000);
+        assert_eq!(v.remove(1), Some(0));
+        assert_eq!(v.swap_remove(0), Some(1000));
+        assert_eq!(v.pop(), Some(98));
+        for x in v.iter_mut() {
+            *x *= 2;
+        }
+        assert_eq!(v.as_slice()[..3], [198, 2, 4]);
+
+        let cloned = v.clone();
+        assert_eq!(cloned, v);
+        assert_eq!(v.into_iter().sum::<u64>(), cloned.iter().sum());
+
+        // 構造体のアラインメントが大きい要素でも使える
+        let words: CompactToyVec<u128> = (0..5).collect();
+        assert_eq!(format!("{:?}", words), "[0, 1, 2, 3, 4]");
+    }
+
+    #[test]
+    fn test_reserve_and_shrink_to_fit() {
+        let mut v = CompactToyVec::new();
+        v.reserve(10);
+        assert_eq!((v.len(), v.capacity()), (0, 10));
+        for i in 0..3 {
+            v.push(i.to_string());
+        }
+        // 足りていればキャパシティは変わらない
+        v.reserve(7);
+        assert_eq!((v.len(), v.capacity()), (3, 10));
+        v.reserve(8);
+        assert_eq!((v.len(), v.capacity()), (3, 11));
+
+        v.shrink_to_fit();
+        assert_eq!((v.len(), v.capacity()), (3, 3));
+        assert_eq!(v.as_slice(), ["0", "1", "2"]);
+        v.push("3".to_string());
+        assert_eq!((v.len(), v.capacity()), (4, 6));
+
+        // 空にして縮めると、領域を確保していない状態に戻る
+        while v.pop().is_some() {}
+        v.shrink_to_fit();
+        assert_eq!((v.len(), v.capacity()), (0, 0));
+        v.shrink_to_fit();
+        v.push("a".to_string());
+        assert_eq!((v.len(), v.capacity()), (1, 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn test_reserve_overflow_panics() {
+        let mut v = CompactToyVec::<u8>::new();
+        v.reserve(u32::MAX as usize + 1);
+    }
+
+    #[test]
+    fn test_drop_elements() {
+        let counter = Rc::new(Cell::new(0));
+        struct Counted(Rc<Cell<usize>>);
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let mut v = CompactToyVec::new();
+        for _ in 0..10 {
+            v.push(Counted(Rc::clone(&counter)));
+        }
+        drop(v.remove(3));
+        assert_eq!(counter.get(), 1);
+        drop(v);
+        assert_eq!(counter.get(), 10);
+
+        // 途中まで取り出したイテレータをドロップしても、残りの要素がドロップされる
+        let v: CompactToyVec<_> = (0..10).map(|_| Counted(Rc::clone(&counter))).collect();
+        let mut iter = v.into_iter();
+        drop(iter.next());
+        drop(iter);
+        assert_eq!(counter.get(), 20);
+    }
+
+    #[test]
+    fn test_capacity_overflow() {
+        assert_eq!(
+            CompactToyVec::<u8>::try_with_capacity(u32::MAX as usize + 1).err(),
+            Some(ToyVecError::CapacityOverflow)
+        );
+
+        // 大きさ0の型なら、メモリを使わずに上限まで確保できる
+        let mut v = CompactToyVec::<()>::with_capacity(u32::MAX as usize);
+        assert_eq!(v.capacity(), u32::MAX as usize);
+        assert_eq!(v.try_reserve(1), Ok(()));
+        v.push(());
+        assert_eq!(
+            v.try_reserve(u32::MAX as usize),
+            Err(ToyVecError::CapacityOverflow)
+        );
+        assert_eq!(v.len(), 1);
+
+        // 上限まで埋まっていれば、追加しようとした要素はエラーと一緒に返ってくる
+        let mut v = CompactToyVec::with_capacity(u32::MAX as usize);
+        unsafe {
+            v.header_mut().len = u32::MAX;
+        }
+        assert_eq!(
+            v.try_push(()),
+            Err(TryPushError::new((), ToyVecError::CapacityOverflow))
+        );
+        assert_eq!(v.len(), u32::MAX as usize);
+    }
+}
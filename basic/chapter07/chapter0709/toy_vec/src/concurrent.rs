@@ -0,0 +1,332 @@
+use crate::ToyVec;
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::atomic::Ordering;
+
+// `RUSTFLAGS="--cfg loom"`でビルドしたときは、アトミック型と`UnsafeCell`をloomのものに差し替える
+// loomはスレッドの実行順をすべて試し、アトミック操作の順序付けの誤りを見つけてくれる
+#[cfg(loom)]
+use loom::{
+    cell::UnsafeCell,
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize},
+};
+#[cfg(not(loom))]
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize};
+
+// loomの`UnsafeCell`と同じ形で使えるようにした`std::cell::UnsafeCell`のラッパー
+#[cfg(not(loom))]
+struct UnsafeCell<T>(std::cell::UnsafeCell<T>);
+
+#[cfg(not(loom))]
+impl<T> UnsafeCell<T> {
+    fn new(value: T) -> Self {
+        Self(std::cell::UnsafeCell::new(value))
+    }
+
+    fn with<R>(&self, f: impl FnOnce(*const T) -> R) -> R {
+        f(self.0.get())
+    }
+
+    fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+        f(self.0.get())
+    }
+}
+
+// 最初のバケットの要素数は2^FIRST_BUCKET_BITS。以降のバケットは直前の2倍の大きさになる
+// loomでは少ない要素数でバケットの確保が競合する場面を試せるよう、小さくしておく
+#[cfg(not(loom))]
+const FIRST_BUCKET_BITS: u32 = 5;
+#[cfg(loom)]
+const FIRST_BUCKET_BITS: u32 = 1;
+const FIRST_BUCKET_SIZE: usize = 1 << FIRST_BUCKET_BITS;
+// バケットの数。全バケットを合わせると`usize`の範囲のインデックスをすべて表せる
+const BUCKETS: usize = (usize::BITS - FIRST_BUCKET_BITS) as usize;
+
+// 要素を1つ格納する場所。`ready`が`true`になった後でだけ`value`を読める
+struct Slot<T> {
+    ready: AtomicBool,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+// バケットを`ToyVec::allocate_in_heap`で確保するために`Default`を実装しておく
+impl<T> Default for Slot<T> {
+    fn default() -> Self {
+        Self {
+            ready: AtomicBool::new(false),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+}
+
+// 複数のスレッドから`&self`で要素を追加できる、追加専用のベクタ
+// `Arc<RwLock<ToyVec<T>>>`と違ってロックを取らないので、追加するスレッド同士が待たされない
+//
+// 要素は大きさが倍々になるバケットに格納する。バケットは一度公開したら移動も解放もしないので、
+// 他のスレッドが要素を追加している最中でも、読み出した要素への参照は有効なまま
+pub struct ConcurrentToyVec<T> {
+    // 各バケットの先頭を指すポインタ。まだ確保していなければヌル
+    buckets: [AtomicPtr<Slot<T>>; BUCKETS],
+    // これまでに予約されたインデックスの数
+    reserved: AtomicUsize,
+}
+
+// 要素の所有権を別スレッドへ渡せて（`push`）、別スレッドから参照できる（`get`）必要がある
+unsafe impl<T: Send> Send for ConcurrentToyVec<T> {}
+unsafe impl<T: Send + Sync> Sync for ConcurrentToyVec<T> {}
+
+impl<T> ConcurrentToyVec<T> {
+    pub fn new() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| AtomicPtr::new(ptr::null_mut())),
+            reserved: AtomicUsize::new(0),
+        }
+    }
+
+    // 要素を追加し、そのインデックスを返す。複数のスレッドから同時に呼べる
+    pub fn push(&self, element: T) -> usize {
+        // インデックスを予約する。ここで他のスレッドとぶつかることはない
+        let index = self.reserved.fetch_add(1, Ordering::Relaxed);
+        let (bucket, offset) = location(index);
+        let slots = self.bucket_or_alloc(bucket);
+        unsafe {
+            let slot = &*slots.add(offset);
+            // 予約したスロットに書き込むのはこのスレッドだけ
+            slot.value
+                .with_mut(|value| (*value).as_mut_ptr().write(element));
+            // `Release`で書き込みを公開する。`Acquire`で`ready`を読んだスレッドには`value`も見える
+            slot.ready.store(true, Ordering::Release);
+        }
+        index
+    }
+
+    // 予約されたインデックスの数を返す
+    // 書き込み途中の要素も数えるので、`len()`未満のインデックスでも`get`が`None`を返すことがある
+    pub fn len(&self) -> usize {
+        self.reserved.load(Ordering::Acquire)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // 書き込みが終わった要素なら参照を返す
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len() {
+            return None;
+        }
+        let (bucket, offset) = location(index);
+        let slots = self.buckets[bucket].load(Ordering::Acquire);
+        if slots.is_null() {
+            return None;
+        }
+        unsafe {
+            let slot = &*slots.add(offset);
+            if slot.ready.load(Ordering::Acquire) {
+                // 書き込みの終わった要素は二度と書き換えられないので、共有の参照を返してよい
+                Some(slot.value.with(|value| &*(*value).as_ptr()))
+            } else {
+                None
+            }
+        }
+    }
+
+    // 先頭から順に要素を返すイテレータを作る
+    // 作成時点の`len()`までを対象とし、書き込み途中の要素に行き当たったらそこで終わる
+    // そのため、返す要素は常に「途中に抜けのない先頭からの並び」になる
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            vec: self,
+            len: self.len(),
+            pos: 0,
+        }
+    }
+
+    // バケットを返す。まだなければ確保して公開する
+    fn bucket_or_alloc(&self, bucket: usize) -> *mut Slot<T> {
+        let current = self.buckets[bucket].load(Ordering::Acquire);
+        if !current.is_null() {
+            return current;
+        }
+
+        let slots = ToyVec::<Slot<T>>::allocate_in_heap(bucket_len(bucket));
+        let new = Box::into_raw(slots) as *mut Slot<T>;
+        match self.buckets[bucket].compare_exchange(
+            ptr::null_mut(),
+            new,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => new,
+            // 他のスレッドが先に公開したので、確保した領域は捨ててそちらを使う
+            Err(winner) => {
+                unsafe { drop(Box::from_raw(slice_ptr(new, bucket))) };
+                winner
+            }
+        }
+    }
+}
+
+impl<T> Default for ConcurrentToyVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for ConcurrentToyVec<T> {
+    fn drop(&mut self) {
+        for bucket in 0..BUCKETS {
+            let slots = self.buckets[bucket].load(Ordering::Acquire);
+            if slots.is_null() {
+                continue;
+            }
+            unsafe {
+                // 書き込み済みの要素だけを破棄してから、バケットを解放する
+                for offset in 0..bucket_len(bucket) {
+                    let slot = &*slots.add(offset);
+                    if slot.ready.load(Ordering::Acquire) {
+                        slot.value
+                            .with_mut(|value| ptr::drop_in_place((*value).as_mut_ptr()));
+                    }
+                }
+                drop(Box::from_raw(slice_ptr(slots, bucket)));
+            }
+        }
+    }
+}
+
+// インデックスを(バケット, バケット内の位置)に変換する
+// バケット`b`は`FIRST_BUCKET_SIZE * (2^b - 1)`番目から始まる
+fn location(index: usize) -> (usize, usize) {
+    let biased = index + FIRST_BUCKET_SIZE;
+    let bucket = (usize::BITS - 1 - biased.leading_zeros() - FIRST_BUCKET_BITS) as usize;
+    (bucket, biased - bucket_len(bucket))
+}
+
+fn bucket_len(bucket: usize) -> usize {
+    FIRST_BUCKET_SIZE << bucket
+}
+
+fn slice_ptr<T>(slots: *mut Slot<T>, bucket: usize) -> *mut [Slot<T>] {
+    ptr::slice_from_raw_parts_mut(slots, bucket_len(bucket))
+}
+
+pub struct Iter<'vec, T> {
+    vec: &'vec ConcurrentToyVec<T>,
+    len: usize,
+    pos: usize,
+}
+
+impl<'vec, T> Iterator for Iter<'vec, T> {
+    type Item = &'vec T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.len {
+            return None;
+        }
+        match self.vec.get(self.pos) {
+            Some(elem) => {
+                self.pos += 1;
+                Some(elem)
+            }
+            // 書き込み途中の要素があれば、そこで打ち切る
+            None => {
+                self.len = self.pos;
+                None
+            }
+        }
+    }
+}
+
+// loomでのテストは`tests/loom_concurrent.rs`にある
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use super::{location, ConcurrentToyVec, FIRST_BUCKET_SIZE};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_location() {
+        assert_eq!(location(0), (0, 0));
+        assert_eq!(location(FIRST_BUCKET_SIZE - 1), (0, FIRST_BUCKET_SIZE - 1));
+        assert_eq!(location(FIRST_BUCKET_SIZE), (1, 0));
+        assert_eq!(location(FIRST_BUCKET_SIZE * 3), (2, 0));
+        assert_eq!(
+            location(usize::MAX - FIRST_BUCKET_SIZE).0,
+            super::BUCKETS - 1
+        );
+    }
+
+    #[test]
+    fn test_push_and_get() {
+        let v = ConcurrentToyVec::new();
+        for i in 0..1000 {
+            assert_eq!(v.push(i.to_string()), i);
+        }
+        assert_eq!(v.len(), 1000);
+        assert_eq!(v.get(999).map(String::as_str), Some("999"));
+        assert_eq!(v.get(1000), None);
+        assert!(v.iter().map(|s| s.parse::<usize>().unwrap()).eq(0..1000));
+    }
+
+    // 多数のスレッドから同時に追加しながら、別スレッドで読み出す
+    #[test]
+    fn test_stress() {
+        const THREADS: usize = 8;
+        const PER_THREAD: usize = 10_000;
+
+        let v = Arc::new(ConcurrentToyVec::new());
+        let writers: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let v = Arc::clone(&v);
+                std::thread::spawn(move || {
+                    for i in 0..PER_THREAD {
+                        v.push((t, i));
+                    }
+                })
+            })
+            .collect();
+
+        let reader = {
+            let v = Arc::clone(&v);
+            std::thread::spawn(move || {
+                let mut max_seen = 0;
+                while max_seen < THREADS * PER_THREAD {
+                    // 途中までしか書き込まれていなくても、読めた要素はすべて正しい値になっている
+                    let mut last = [None; THREADS];
+                    let mut count = 0;
+                    for &(t, i) in v.iter() {
+                        // 同じスレッドが追加した要素は追加した順に並ぶ
+                        assert!(last[t].is_none_or(|prev| prev < i));
+                        last[t] = Some(i);
+                        count += 1;
+                    }
+                    max_seen = max_seen.max(count);
+                }
+            })
+        };
+
+        for w in writers {
+            w.join().expect("writer panicked");
+        }
+        reader.join().expect("reader panicked");
+
+        assert_eq!(v.len(), THREADS * PER_THREAD);
+        let mut counts = [0; THREADS];
+        for &(t, _) in v.iter() {
+            counts[t] += 1;
+        }
+        assert_eq!(counts, [PER_THREAD; THREADS]);
+    }
+
+    #[test]
+    fn test_drop_elements() {
+        let counter = Arc::new(());
+        {
+            let v = ConcurrentToyVec::new();
+            for _ in 0..100 {
+                v.push(Arc::clone(&counter));
+            }
+            assert_eq!(Arc::strong_count(&counter), 101);
+        }
+        assert_eq!(Arc::strong_count(&counter), 1);
+    }
+}
@@ -0,0 +1,337 @@
+// `ToyVec`の要素を1つずつたどりながら、その場で挿入や削除をするためのカーソル
+//
+// 標準ライブラリの`LinkedList::cursor_front`/`cursor_front_mut`にならい、読むだけのカーソルと
+// 編集できるカーソルを名前で分けている
+// - `cursor_front`、`cursor_at`: 読むだけの`Cursor`を返す（`&self`を借りる）
+// - `cursor_front_mut`、`cursor_at_mut`: 挿入や削除ができる`CursorMut`を返す（`&mut self`を借りる）
+// `i`番目の要素を指して編集したいときは`cursor_at_mut(i)`を使う
+use crate::ToyVec;
+use std::mem;
+
+// 挿入のために最初に空ける隙間の要素数
+const MIN_GAP: usize = 4;
+
+impl<T: Default> ToyVec<T> {
+    // 先頭の要素を指すカーソルを作る
+    pub fn cursor_front(&self) -> Cursor<'_, T> {
+        self.cursor_at(0)
+    }
+
+    // `index`番目の要素を指すカーソルを作る。`index == len()`なら末尾の先（どの要素も指さない位置）を指す
+    pub fn cursor_at(&self, index: usize) -> Cursor<'_, T> {
+        assert!(
+            index <= self.len,
+            "cursor index (is {}) should be <= len (is {})",
+            index,
+            self.len
+        );
+        Cursor { vec: self, index }
+    }
+
+    // 先頭の要素を指す、編集できるカーソルを作る
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        self.cursor_at_mut(0)
+    }
+
+    // `index`番目の要素を指す、編集できるカーソルを作る
+    pub fn cursor_at_mut(&mut self, index: usize) -> CursorMut<'_, T> {
+        assert!(
+            index <= self.len,
+            "cursor index (is {}) should be <= len (is {})",
+            index,
+            self.len
+        );
+        CursorMut {
+            vec: self,
+            write: index,
+            read: index,
+            next_gap: MIN_GAP,
+        }
+    }
+}
+
+// 要素を読むだけのカーソル
+pub struct Cursor<'vec, T> {
+    vec: &'vec ToyVec<T>,
+    index: usize,
+}
+
+impl<'vec, T: Default> Cursor<'vec, T> {
+    // カーソルが指す要素のインデックス
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    // カーソルが指す要素。末尾の先なら`None`
+    pub fn current(&self) -> Option<&'vec T> {
+        self.vec.get(self.index)
+    }
+
+    // `current`と同じ。`CursorMut::peek`と名前をそろえるために用意している
+    pub fn peek(&self) -> Option<&'vec T> {
+        self.current()
+    }
+
+    pub fn peek_next(&self) -> Option<&'vec T> {
+        self.vec.get(self.index + 1)
+    }
+
+    pub fn peek_prev(&self) -> Option<&'vec T> {
+        self.vec.get(self.index.checked_sub(1)?)
+    }
+
+    // 次の要素へ進む。すでに末尾の先なら`false`を返す
+    pub fn move_next(&mut self) -> bool {
+        if self.index == self.vec.len() {
+            return false;
+        }
+        self.index += 1;
+        true
+    }
+
+    // 前の要素へ戻る。すでに先頭なら`false`を返す
+    pub fn move_prev(&mut self) -> bool {
+        if self.index == 0 {
+            return false;
+        }
+        self.index -= 1;
+        true
+    }
+}
+
+// 要素を編集できるカーソル
+//
+// 編集は、カーソルの位置に空けた隙間（ギャップ）を使ってまとめて行う
+// `elements[..write]`がカーソルより前の要素、`elements[read..len]`がカーソルの指す要素とそれより後ろの要素で、
+// その間の`elements[write..read]`が隙間（`T::default()`が入っている）
+// 要素を削除すると隙間が1つ広がるだけで、後ろの要素はずらさない。カーソルを進めると要素を1つ隙間の前へ移す
+// そのため、先頭から末尾までたどりながら多数の要素を削除しても、全体でO(n)で済む
+// 隙間はカーソルを破棄するときに詰める
+pub struct CursorMut<'vec, T: Default> {
+    vec: &'vec mut ToyVec<T>,
+    write: usize,
+    read: usize,
+    // 次に隙間を空けるときの大きさ。空けるたびに2倍にして、挿入を繰り返したときの要素の移動を抑える
+    next_gap: usize,
+}
+
+impl<'vec, T: Default> CursorMut<'vec, T> {
+    // カーソルが指す要素のインデックス
+    pub fn index(&self) -> usize {
+        self.write
+    }
+
+    // カーソルが指す要素。末尾の先なら`None`
+    pub fn current(&mut self) -> Option<&mut T> {
+        if self.read < self.vec.len {
+            Some(&mut self.vec.elements[self.read])
+        } else {
+            None
+        }
+    }
+
+    // カーソルが指す要素を読む。`current`と違い`&self`で呼べる
+    pub fn peek(&self) -> Option<&T> {
+        self.vec.elements[..self.vec.len].get(self.read)
+    }
+
+    pub fn peek_next(&self) -> Option<&T> {
+        if self.read + 1 < self.vec.len {
+            Some(&self.vec.elements[self.read + 1])
+        } else {
+            None
+        }
+    }
+
+    pub fn peek_prev(&self) -> Option<&T> {
+        Some(&self.vec.elements[self.write.checked_sub(1)?])
+    }
+
+    // 次の要素へ進む。カーソルが指していた要素を隙間の前へ移す
+    pub fn move_next(&mut self) -> bool {
+        if self.read == self.vec.len {
+            return false;
+        }
+        self.vec.elements.swap(self.write, self.read);
+        self.write += 1;
+        self.read += 1;
+        true
+    }
+
+    // 前の要素へ戻る。直前の要素を隙間の後ろへ移す
+    pub fn move_prev(&mut self) -> bool {
+        if self.write == 0 {
+            return false;
+        }
+        self.write -= 1;
+        self.read -= 1;
+        self.vec.elements.swap(self.write, self.read);
+        true
+    }
+
+    // カーソルが指す要素の前に挿入する。カーソルは同じ要素を指したまま
+    pub fn insert_before(&mut self, element: T) {
+        self.reserve_gap();
+        self.vec.elements[self.write] = element;
+        self.write += 1;
+    }
+
+    // カーソルが指す要素の後ろに挿入する。カーソルは同じ要素を指したまま
+    // 末尾の先を指しているときは、末尾に追加する
+    pub fn insert_after(&mut self, element: T) {
+        if self.read == self.vec.len {
+            self.insert_before(element);
+            return;
+        }
+        self.reserve_gap();
+        // 指している要素を隙間の末尾へずらし、空いた場所に挿入する
+        let current = mem::replace(&mut self.vec.elements[self.read], element);
+        self.read -= 1;
+        self.vec.elements[self.read] = current;
+    }
+
+    // カーソルが指す要素を取り除いて返す。カーソルは次の要素を指す
+    pub fn remove_current(&mut self) -> Option<T> {
+        if self.read == self.vec.len {
+            return None;
+        }
+        let element = mem::take(&mut self.vec.elements[self.read]);
+        self.read += 1;
+        Some(element)
+    }
+
+    // カーソルが指す要素から末尾までを切り離して返す。カーソルは末尾の先を指す
+    pub fn split_here(&mut self) -> ToyVec<T> {
+        let mut tail = ToyVec::with_capacity(self.vec.len - self.read);
+        for i in self.read..self.vec.len {
+            tail.push(mem::take(&mut self.vec.elements[i]));
+        }
+        self.vec.len = self.write;
+        self.read = self.write;
+        tail
+    }
+
+    // 隙間がなければ、後ろの要素をずらして隙間を空ける
+    fn reserve_gap(&mut self) {
+        if self.read > self.write {
+            return;
+        }
+        let gap = self.next_gap;
+        self.next_gap *= 2;
+        while self.vec.capacity() < self.vec.len + gap {
+            self.vec.grow();
+        }
+        let end = self.vec.len + gap;
+        self.vec.elements[self.read..end].rotate_right(gap);
+        self.read += gap;
+        self.vec.len = end;
+    }
+}
+
+// カーソルを破棄するときに隙間を詰める
+impl<'vec, T: Default> Drop for CursorMut<'vec, T> {
+    fn drop(&mut self) {
+        let gap = self.read - self.write;
+        if gap > 0 {
+            self.vec.elements[self.write..self.vec.len].rotate_left(gap);
+            self.vec.len -= gap;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ToyVec;
+
+    fn toy_vec(items: &[i32]) -> ToyVec<i32> {
+        let mut v = ToyVec::new();
+        for &i in items {
+            v.push(i);
+        }
+        v
+    }
+
+    #[test]
+    fn test_cursor() {
+        let v = toy_vec(&[1, 2, 3]);
+        let mut cursor = v.cursor_at(1);
+        assert_eq!(cursor.current(), Some(&2));
+        assert_eq!(cursor.peek(), Some(&2));
+        assert_eq!(cursor.peek_prev(), Some(&1));
+        assert_eq!(cursor.peek_next(), Some(&3));
+        assert!(cursor.move_next());
+        assert!(cursor.move_next());
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.peek(), None);
+        assert!(!cursor.move_next());
+        assert!(v.cursor_front().peek_prev().is_none());
+    }
+
+    #[test]
+    fn test_remove_while_traversing() {
+        let mut v = toy_vec(&(0..10_000).collect::<Vec<_>>());
+        {
+            let mut cursor = v.cursor_front_mut();
+            while let Some(x) = cursor.current() {
+                if *x % 3 == 0 {
+                    cursor.remove_current();
+                } else {
+                    *x *= 10;
+                    cursor.move_next();
+                }
+            }
+        }
+        let expected: Vec<_> = (0..10_000).filter(|x| x % 3 != 0).map(|x| x * 10).collect();
+        assert_eq!(v.as_slice(), &expected[..]);
+    }
+
+    #[test]
+    fn test_insert_and_move_back() {
+        let mut v = toy_vec(&[1, 3, 5]);
+        {
+            let mut cursor = v.cursor_at_mut(1);
+            cursor.insert_before(2);
+            cursor.insert_after(4);
+            assert_eq!(cursor.index(), 2);
+            assert_eq!(cursor.current().copied(), Some(3));
+            assert_eq!(cursor.peek(), Some(&3));
+            assert_eq!(cursor.peek_next(), Some(&4));
+            assert_eq!(cursor.peek_prev(), Some(&2));
+
+            // 隙間がある状態で戻っても、要素の順序は保たれる
+            assert!(cursor.move_prev());
+            assert!(cursor.move_prev());
+            assert!(!cursor.move_prev());
+            cursor.insert_before(0);
+            while cursor.move_next() {}
+            assert_eq!(cursor.peek(), None);
+            cursor.insert_after(6);
+        }
+        assert_eq!(v.as_slice(), &[0, 1, 2, 3, 4, 5, 6]);
+
+        // 挿入を繰り返しても要素が正しく並ぶ
+        {
+            let mut cursor = v.cursor_at_mut(3);
+            for i in 0..100 {
+                cursor.insert_before(100 + i);
+            }
+        }
+        assert_eq!(v.len(), 107);
+        assert_eq!(v.get(2), Some(&2));
+        assert_eq!(v.get(3), Some(&100));
+        assert_eq!(v.get(103), Some(&3));
+    }
+
+    #[test]
+    fn test_split_here() {
+        let mut v = toy_vec(&[1, 2, 3, 4, 5]);
+        let tail = {
+            let mut cursor = v.cursor_at_mut(1);
+            cursor.remove_current();
+            cursor.move_next();
+            cursor.split_here()
+        };
+        assert_eq!(v.as_slice(), &[1, 3]);
+        assert_eq!(tail.as_slice(), &[4, 5]);
+    }
+}
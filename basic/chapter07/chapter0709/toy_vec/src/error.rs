@@ -0,0 +1,213 @@
+// `ToyVec`の操作が失敗した理由を表すエラーと、それを返す`try_*`メソッド
+// `get`や`remove`は範囲外で`None`を返すだけなので、なぜ失敗したかをログに残したいときはこちらを使う
+use crate::ToyVec;
+use std::alloc::Layout;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToyVecError {
+    // インデックスが範囲外
+    IndexOutOfBounds { index: usize, len: usize },
+    // 必要なキャパシティが`usize`やメモリのレイアウトで表せる大きさを超える
+    CapacityOverflow,
+    // メモリを確保できなかった
+    AllocFailed,
+    // 最大の長さに達していて追加できない
+    Full,
+}
+
+impl fmt::Display for ToyVecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ToyVecError::IndexOutOfBounds { index, len } => {
+                write!(f, "index {} is out of bounds (len is {})", index, len)
+            }
+            ToyVecError::CapacityOverflow => write!(f, "capacity overflow"),
+            ToyVecError::AllocFailed => write!(f, "memory allocation failed"),
+            ToyVecError::Full => write!(f, "vector is full"),
+        }
+    }
+}
+
+impl Error for ToyVecError {}
+
+// `try_push`や`try_insert`が失敗したときのエラー
+// 要素の所有権は呼び出し側から受け取っているので、捨てずにエラーと一緒に返す
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TryPushError<T> {
+    element: T,
+    error: ToyVecError,
+}
+
+impl<T> TryPushError<T> {
+    pub fn new(element: T, error: ToyVecError) -> Self {
+        Self { element, error }
+    }
+
+    // 失敗した理由
+    pub fn error(&self) -> ToyVecError {
+        self.error
+    }
+
+    // 追加できなかった要素を取り出す
+    pub fn into_element(self) -> T {
+        self.element
+    }
+}
+
+impl<T> fmt::Display for TryPushError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl<T: fmt::Debug> Error for TryPushError<T> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl<T: Default> ToyVec<T> {
+    // `allocate_in_heap`の失敗を返す版
+    pub fn try_allocate_in_heap(size: usize) -> Result<Box<[T]>, ToyVecError> {
+        // 大きすぎて領域のレイアウトが作れない場合を先に区別しておく
+        Layout::array::<T>(size).map_err(|_| ToyVecError::CapacityOverflow)?;
+        let mut elements = Vec::new();
+        elements
+            .try_reserve_exact(size)
+            .map_err(|_| ToyVecError::AllocFailed)?;
+        elements.extend(std::iter::repeat_with(Default::default).take(size));
+        Ok(elements.into_boxed_slice())
+    }
+
+    // 少なくとも`additional`個の要素を追加できるようにする。`reserve`の失敗を返す版
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), ToyVecError> {
+        let required = self
+            .len
+            .checked_add(additional)
+            .ok_or(ToyVecError::CapacityOverflow)?;
+        if required > self.capacity() {
+            self.try_reallocate(required)?;
+        }
+        Ok(())
+    }
+
+    // 末尾に要素を追加する。領域を広げられなければ要素とエラーを返し、ベクタは変更しない
+    pub fn try_push(&mut self, element: T) -> Result<(), TryPushError<T>> {
+        if self.len == self.capacity() {
+            if let Err(e) = self.try_grow() {
+                return Err(TryPushError::new(element, e));
+            }
+        }
+        self.push(element);
+        Ok(())
+    }
+
+    pub fn try_insert(&mut self, index: usize, element: T) -> Result<(), TryPushError<T>> {
+        if index > self.len {
+            let e = ToyVecError::IndexOutOfBounds {
+                index,
+                len: self.len,
+            };
+            return Err(TryPushError::new(element, e));
+        }
+        if self.len == self.capacity() {
+            if let Err(e) = self.try_grow() {
+                return Err(TryPushError::new(element, e));
+            }
+        }
+        self.insert(index, element);
+        Ok(())
+    }
+
+    pub fn try_get(&self, index: usize) -> Result<&T, ToyVecError> {
+        self.get(index).ok_or(ToyVecError::IndexOutOfBounds {
+            index,
+            len: self.len,
+        })
+    }
+
+    pub fn try_remove(&mut self, index: usize) -> Result<T, ToyVecError> {
+        let len = self.len;
+        self.remove(index)
+            .ok_or(ToyVecError::IndexOutOfBounds { index, len })
+    }
+
+    // `iter`の要素を末尾に追加する。途中で失敗した場合、それまでに追加した要素は残る
+    // （追加できなかった要素は捨てる）
+    pub fn try_extend<I: IntoIterator<Item = T>>(&mut self, iter: I) -> Result<(), ToyVecError> {
+        let iter = iter.into_iter();
+        // 要素数の下限が分かれば、先にまとめて確保しておく
+        self.try_reserve(iter.size_hint().0)?;
+        for element in iter {
+            self.try_push(element).map_err(|e| e.error())?;
+        }
+        Ok(())
+    }
+
+    // `grow`の失敗を返す版
+    fn try_grow(&mut self) -> Result<(), ToyVecError> {
+        let capacity = if self.capacity() == 0 {
+            1
+        } else {
+            self.capacity()
+                .checked_mul(2)
+                .ok_or(ToyVecError::CapacityOverflow)?
+        };
+        self.try_reallocate(capacity)
+    }
+
+    fn try_reallocate(&mut self, capacity: usize) -> Result<(), ToyVecError> {
+        self.move_into(Self::try_allocate_in_heap(capacity)?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ToyVecError;
+    use crate::ToyVec;
+
+    #[test]
+    fn test_index_errors() {
+        let mut v = ToyVec::new();
+        v.try_extend(0..3).unwrap();
+        assert_eq!(v.capacity(), 3);
+        assert_eq!(v.try_get(1), Ok(&1));
+        assert_eq!(
+            v.try_get(3),
+            Err(ToyVecError::IndexOutOfBounds { index: 3, len: 3 })
+        );
+        // 追加できなかった要素はエラーと一緒に返ってくる
+        let e = v.try_insert(5, 10).unwrap_err();
+        assert_eq!(
+            e.error(),
+            ToyVecError::IndexOutOfBounds { index: 5, len: 3 }
+        );
+        assert_eq!(e.into_element(), 10);
+        v.try_insert(3, 10).unwrap();
+        assert_eq!(v.try_remove(0), Ok(0));
+        assert_eq!(
+            v.try_remove(3).unwrap_err().to_string(),
+            "index 3 is out of bounds (len is 3)"
+        );
+        assert_eq!(v.as_slice(), &[1, 2, 10]);
+    }
+
+    #[test]
+    fn test_capacity_errors() {
+        let mut v: ToyVec<u64> = ToyVec::new();
+        v.try_push(1).unwrap();
+        assert_eq!(
+            v.try_reserve(usize::MAX),
+            Err(ToyVecError::CapacityOverflow)
+        );
+        // `usize`では表せても、メモリのレイアウトとして大きすぎる
+        assert_eq!(
+            v.try_reserve(usize::MAX / 4),
+            Err(ToyVecError::CapacityOverflow)
+        );
+        assert_eq!(v.as_slice(), &[1]);
+    }
+}
@@ -0,0 +1,193 @@
+// C言語から`ToyVec`を使うための関数（C ABI）。宣言は`include/toy_vec.h`にある
+//
+// 所有権の規則:
+// - `toyvec_*_new`が返したポインタの所有者は呼び出し側で、使い終わったら同じ型の`toyvec_*_free`で
+//   一度だけ解放する。`free`したポインタを再び使ってはならない
+// - `toyvec_*_as_ptr`が返すポインタは`ToyVec`の領域を借りているだけで、解放してはならない
+//   次に`push`や`free`を呼ぶまでの間だけ有効（`push`は領域を確保し直すことがある）
+// - 要素は値でやりとりするので、`push`した値や`pop`と`get`で受け取った値の所有権の問題は生じない
+// - 同じ`ToyVec`を複数のスレッドから同時に使ってはならない
+//
+// 引数のポインタが`NULL`の場合は何もせず、失敗を表す値（`false`や0）を返す
+use crate::ToyVec;
+use std::ptr;
+
+// 要素の型ごとに同じ関数群を定義する
+macro_rules! ffi_toy_vec {
+    ($t:ty, $new:ident, $free:ident, $push:ident, $pop:ident, $get:ident, $len:ident, $as_ptr:ident) => {
+        /// 空の`ToyVec`を作る。戻り値は対応する`free`関数で解放すること
+        #[no_mangle]
+        pub extern "C" fn $new() -> *mut ToyVec<$t> {
+            Box::into_raw(Box::new(ToyVec::new()))
+        }
+
+        /// `ToyVec`を解放する。`NULL`なら何もしない
+        ///
+        /// # Safety
+        ///
+        /// `vec`は`NULL`か、同じ型の`new`関数が返してまだ解放していないポインタでなければならない
+        #[no_mangle]
+        pub unsafe extern "C" fn $free(vec: *mut ToyVec<$t>) {
+            if !vec.is_null() {
+                drop(Box::from_raw(vec));
+            }
+        }
+
+        /// 末尾に要素を追加する
+        ///
+        /// # Safety
+        ///
+        /// `vec`は`NULL`か、同じ型の`new`関数が返してまだ解放していないポインタでなければならない
+        #[no_mangle]
+        pub unsafe extern "C" fn $push(vec: *mut ToyVec<$t>, value: $t) {
+            if let Some(vec) = vec.as_mut() {
+                vec.push(value);
+            }
+        }
+
+        /// 末尾の要素を取り除いて`out`に書き込む。空なら`false`を返し、`out`は変更しない
+        ///
+        /// # Safety
+        ///
+        /// `vec`は`free`関数と同じ条件を満たし、`out`は`NULL`か書き込み可能な領域を指していなければならない
+        #[no_mangle]
+        pub unsafe extern "C" fn $pop(vec: *mut ToyVec<$t>, out: *mut $t) -> bool {
+            match (vec.as_mut(), out.is_null()) {
+                (Some(vec), false) => match vec.pop() {
+                    Some(value) => {
+                        out.write(value);
+                        true
+                    }
+                    None => false,
+                },
+                _ => false,
+            }
+        }
+
+        /// `index`番目の要素を`out`に書き込む。範囲外なら`false`を返し、`out`は変更しない
+        ///
+        /// # Safety
+        ///
+        /// `vec`は`free`関数と同じ条件を満たし、`out`は`NULL`か書き込み可能な領域を指していなければならない
+        #[no_mangle]
+        pub unsafe extern "C" fn $get(vec: *const ToyVec<$t>, index: usize, out: *mut $t) -> bool {
+            match (vec.as_ref(), out.is_null()) {
+                (Some(vec), false) => match vec.get(index) {
+                    Some(value) => {
+                        out.write(*value);
+                        true
+                    }
+                    None => false,
+                },
+                _ => false,
+            }
+        }
+
+        /// 要素数を返す
+        ///
+        /// # Safety
+        ///
+        /// `vec`は`NULL`か、同じ型の`new`関数が返してまだ解放していないポインタでなければならない
+        #[no_mangle]
+        pub unsafe extern "C" fn $len(vec: *const ToyVec<$t>) -> usize {
+            vec.as_ref().map_or(0, |vec| vec.len())
+        }
+
+        /// 先頭の要素へのポインタを返す。`len`個の要素が連続して並んでいる
+        /// 空の場合もダングリングでないとは限らないので、`len`が0なら読んではならない
+        ///
+        /// # Safety
+        ///
+        /// `vec`は`NULL`か、同じ型の`new`関数が返してまだ解放していないポインタでなければならない
+        #[no_mangle]
+        pub unsafe extern "C" fn $as_ptr(vec: *const ToyVec<$t>) -> *const $t {
+            vec.as_ref()
+                .map_or(ptr::null(), |vec| vec.as_slice().as_ptr())
+        }
+    };
+}
+
+ffi_toy_vec!(
+    i32,
+    toyvec_i32_new,
+    toyvec_i32_free,
+    toyvec_i32_push,
+    toyvec_i32_pop,
+    toyvec_i32_get,
+    toyvec_i32_len,
+    toyvec_i32_as_ptr
+);
+
+ffi_toy_vec!(
+    u8,
+    toyvec_bytes_new,
+    toyvec_bytes_free,
+    toyvec_bytes_push,
+    toyvec_bytes_pop,
+    toyvec_bytes_get,
+    toyvec_bytes_len,
+    toyvec_bytes_as_ptr
+);
+
+/// `data`から`len`バイトを末尾に追加する
+///
+/// # Safety
+///
+/// `vec`は`NULL`か`toyvec_bytes_new`が返してまだ解放していないポインタで、
+/// `data`は`len`バイト読み取れる領域を指していなければならない（`len`が0なら`NULL`でもよい）
+#[no_mangle]
+pub unsafe extern "C" fn toyvec_bytes_extend(vec: *mut ToyVec<u8>, data: *const u8, len: usize) {
+    let vec = match vec.as_mut() {
+        Some(vec) => vec,
+        None => return,
+    };
+    if len == 0 || data.is_null() {
+        return;
+    }
+    for &b in std::slice::from_raw_parts(data, len) {
+        vec.push(b);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_i32_functions() {
+        unsafe {
+            let v = toyvec_i32_new();
+            for i in 0..10 {
+                toyvec_i32_push(v, i * i);
+            }
+            assert_eq!(toyvec_i32_len(v), 10);
+            let mut out = -1;
+            assert!(toyvec_i32_get(v, 3, &mut out));
+            assert_eq!(out, 9);
+            assert!(!toyvec_i32_get(v, 10, &mut out));
+            assert!(toyvec_i32_pop(v, &mut out));
+            assert_eq!(out, 81);
+            let slice = std::slice::from_raw_parts(toyvec_i32_as_ptr(v), toyvec_i32_len(v));
+            assert_eq!(slice[8], 64);
+            toyvec_i32_free(v);
+
+            // `NULL`を渡しても何も起きない
+            assert_eq!(toyvec_i32_len(ptr::null()), 0);
+            assert!(!toyvec_i32_pop(ptr::null_mut(), &mut out));
+            toyvec_i32_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn test_bytes_functions() {
+        unsafe {
+            let v = toyvec_bytes_new();
+            let data = b"hello";
+            toyvec_bytes_extend(v, data.as_ptr(), data.len());
+            toyvec_bytes_push(v, b'!');
+            let bytes = std::slice::from_raw_parts(toyvec_bytes_as_ptr(v), toyvec_bytes_len(v));
+            assert_eq!(bytes, b"hello!");
+            toyvec_bytes_free(v);
+        }
+    }
+}
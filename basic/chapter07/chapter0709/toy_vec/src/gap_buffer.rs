@@ -0,0 +1,391 @@
+use crate::ToyVec;
+use std::fmt;
+use std::mem;
+
+// 領域が足りなくなったときに、最低限確保する要素数
+const MIN_CAPACITY: usize = 16;
+
+// ギャップバッファ
+// 領域の途中に空き（ギャップ）を置き、カーソルの位置をギャップの先頭と一致させておく
+// カーソルの位置での挿入と削除はギャップの端を動かすだけなのでO(1)で、後ろの要素をずらさなくて済む
+// カーソルを動かすと、移動した距離の分だけ要素をギャップの反対側へ移す
+pub struct GapBuffer<T> {
+    // `buf[gap_start..gap_end]`がギャップ。ギャップ内の要素は`T::default()`
+    // ギャップも含めた領域全体を`ToyVec`の要素として持つので、`buf.len()`は領域の大きさになる
+    buf: ToyVec<T>,
+    gap_start: usize,
+    gap_end: usize,
+}
+
+impl<T: Default> GapBuffer<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut buf = ToyVec::with_capacity(capacity);
+        for _ in 0..capacity {
+            buf.push(T::default());
+        }
+        Self {
+            buf,
+            gap_start: 0,
+            gap_end: capacity,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buf.len() - self.gap_len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // カーソルの位置。カーソルは`cursor() - 1`番目と`cursor()`番目の要素の間にある
+    pub fn cursor(&self) -> usize {
+        self.gap_start
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index < self.gap_start {
+            self.buf.get(index)
+        } else if index < self.len() {
+            self.buf.get(index + self.gap_len())
+        } else {
+            None
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        let buf = self.buf.as_slice();
+        buf[..self.gap_start]
+            .iter()
+            .chain(buf[self.gap_end..].iter())
+    }
+
+    // カーソルを`pos`へ動かす。間にある要素をギャップの反対側へ移す
+    pub fn move_to(&mut self, pos: usize) {
+        assert!(
+            pos <= self.len(),
+            "cursor position (is {}) should be <= len (is {})",
+            pos,
+            self.len()
+        );
+        // 間の要素を1つずつギャップの反対側の端と入れ替える。ギャップの大きさによらず、移動した距離の分だけで済む
+        let buf = self.buf.as_mut_slice();
+        while pos < self.gap_start {
+            self.gap_start -= 1;
+            self.gap_end -= 1;
+            buf.swap(self.gap_start, self.gap_end);
+        }
+        while pos > self.gap_start {
+            buf.swap(self.gap_start, self.gap_end);
+            self.gap_start += 1;
+            self.gap_end += 1;
+        }
+    }
+
+    // カーソルを1つ左へ動かす。先頭にいれば`false`を返す
+    pub fn move_left(&mut self) -> bool {
+        if self.gap_start == 0 {
+            return false;
+        }
+        self.move_to(self.gap_start - 1);
+        true
+    }
+
+    // カーソルを1つ右へ動かす。末尾にいれば`false`を返す
+    pub fn move_right(&mut self) -> bool {
+        if self.gap_end == self.buf.len() {
+            return false;
+        }
+        self.move_to(self.gap_start + 1);
+        true
+    }
+
+    // カーソルの位置に要素を挿入し、カーソルを挿入した要素の後ろへ進める
+    pub fn insert(&mut self, element: T) {
+        if self.gap_len() == 0 {
+            self.grow();
+        }
+        self.buf.as_mut_slice()[self.gap_start] = element;
+        self.gap_start += 1;
+    }
+
+    // カーソルの直前の要素を削除する（バックスペース）
+    pub fn delete_backward(&mut self) -> Option<T> {
+        if self.gap_start == 0 {
+            return None;
+        }
+        self.gap_start -= 1;
+        Some(mem::take(&mut self.buf.as_mut_slice()[self.gap_start]))
+    }
+
+    // カーソルの直後の要素を削除する（デリート）
+    pub fn delete_forward(&mut self) -> Option<T> {
+        if self.gap_end == self.buf.len() {
+            return None;
+        }
+        let element = mem::take(&mut self.buf.as_mut_slice()[self.gap_end]);
+        self.gap_end += 1;
+        Some(element)
+    }
+
+    fn gap_len(&self) -> usize {
+        self.gap_end - self.gap_start
+    }
+
+    // 領域を2倍にし、ギャップより後ろの要素を新しい領域の末尾へ移す
+    fn grow(&mut self) {
+        let capacity = (self.buf.len() * 2).max(MIN_CAPACITY);
+        let tail_len = self.buf.len() - self.gap_end;
+        let mut old = mem::replace(&mut self.buf, ToyVec::with_capacity(capacity)).into_iter();
+        for element in old.by_ref().take(self.gap_start) {
+            self.buf.push(element);
+        }
+        // 広げたギャップの分は`T::default()`で埋め、古いギャップの要素は読み飛ばす
+        for _ in 0..capacity - self.gap_start - tail_len {
+            self.buf.push(T::default());
+        }
+        for element in old.skip(self.gap_len()) {
+            self.buf.push(element);
+        }
+        self.gap_end = capacity - tail_len;
+    }
+}
+
+impl<T: Default> Default for GapBuffer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// テキストとして使う場合の、行と列によるアクセス
+// 行と列はどちらも0から数え、列は行頭からの文字数
+// 改行の位置は覚えておかないので、行番号が絡む計算（`line_col`、`line_start`など）は先頭から
+// 数え直し、位置に比例する時間がかかる。行番号で頻繁に移動するなら、部分木ごとに改行の数を持つ
+// `ToyRope`を使う。`move_up`と`move_down`はカーソルの前後の行だけを調べるので、行の長さの分で済む
+impl GapBuffer<char> {
+    pub fn from_text(text: &str) -> Self {
+        let mut buffer = Self::with_capacity(text.chars().count() + MIN_CAPACITY);
+        buffer.insert_str(text);
+        buffer
+    }
+
+    // カーソルの位置に文字列を挿入する
+    pub fn insert_str(&mut self, text: &str) {
+        for c in text.chars() {
+            self.insert(c);
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.iter().filter(|&&c| c == '\n').count() + 1
+    }
+
+    // `pos`番目の文字の(行, 列)を返す
+    pub fn line_col(&self, pos: usize) -> (usize, usize) {
+        let mut line_col = (0, 0);
+        for &c in self.iter().take(pos) {
+            line_col = if c == '\n' {
+                (line_col.0 + 1, 0)
+            } else {
+                (line_col.0, line_col.1 + 1)
+            };
+        }
+        line_col
+    }
+
+    // `line`行目の先頭の位置を返す
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        if line == 0 {
+            return Some(0);
+        }
+        self.iter()
+            .enumerate()
+            .filter(|(_, &c)| c == '\n')
+            .nth(line - 1)
+            .map(|(pos, _)| pos + 1)
+    }
+
+    // `line`行目の文字数（改行を除く）を返す
+    pub fn line_len(&self, line: usize) -> Option<usize> {
+        let start = self.line_start(line)?;
+        Some(self.iter().skip(start).take_while(|&&c| c != '\n').count())
+    }
+
+    // `line`行目の内容を返す（改行を除く）
+    pub fn line(&self, line: usize) -> Option<String> {
+        let start = self.line_start(line)?;
+        Some(
+            self.iter()
+                .skip(start)
+                .take_while(|&&c| c != '\n')
+                .collect(),
+        )
+    }
+
+    // (行, 列)を文字の位置に変換する。列は行末（改行の直前）まで指定できる
+    pub fn position(&self, line: usize, col: usize) -> Option<usize> {
+        if col > self.line_len(line)? {
+            return None;
+        }
+        Some(self.line_start(line)? + col)
+    }
+
+    // カーソルを(行, 列)へ動かす。範囲外なら`false`を返す
+    pub fn move_to_line_col(&mut self, line: usize, col: usize) -> bool {
+        match self.position(line, col) {
+            Some(pos) => {
+                self.move_to(pos);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // カーソルを上の行の同じ列へ動かす。上の行が短ければ行末へ動かす
+    pub fn move_up(&mut self) -> bool {
+        let start = self.start_of_line(self.cursor());
+        if start == 0 {
+            return false;
+        }
+        let col = self.cursor() - start;
+        // `start - 1`は上の行の末尾の改行
+        let prev_start = self.start_of_line(start - 1);
+        self.move_to(prev_start + col.min(start - 1 - prev_start));
+        true
+    }
+
+    // カーソルを下の行の同じ列へ動かす。下の行が短ければ行末へ動かす
+    pub fn move_down(&mut self) -> bool {
+        let end = self.end_of_line(self.cursor());
+        if end == self.len() {
+            return false;
+        }
+        let col = self.cursor() - self.start_of_line(self.cursor());
+        let next_start = end + 1;
+        let next_len = self.end_of_line(next_start) - next_start;
+        self.move_to(next_start + col.min(next_len));
+        true
+    }
+
+    // `pos`を含む行の先頭の位置。直前の改行まで戻って探す
+    fn start_of_line(&self, pos: usize) -> usize {
+        (0..pos)
+            .rev()
+            .find(|&i| self.get(i) == Some(&'\n'))
+            .map_or(0, |i| i + 1)
+    }
+
+    // `pos`を含む行の末尾の位置（改行の位置、最後の行なら長さ）。直後の改行まで進んで探す
+    fn end_of_line(&self, pos: usize) -> usize {
+        (pos..self.len())
+            .find(|&i| self.get(i) == Some(&'\n'))
+            .unwrap_or(self.len())
+    }
+}
+
+impl fmt::Display for GapBuffer<char> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for c in self.iter() {
+            write!(f, "{}", c)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GapBuffer;
+
+    #[test]
+    fn test_insert_and_delete_at_cursor() {
+        let mut buffer = GapBuffer::new();
+        for i in 0..100 {
+            buffer.insert(i);
+        }
+        buffer.move_to(50);
+        buffer.insert(-1);
+        assert_eq!(buffer.cursor(), 51);
+        assert_eq!(buffer.get(50), Some(&-1));
+        assert_eq!(buffer.get(51), Some(&50));
+        assert_eq!(buffer.len(), 101);
+
+        assert_eq!(buffer.delete_backward(), Some(-1));
+        assert_eq!(buffer.delete_forward(), Some(50));
+        buffer.move_to(0);
+        assert_eq!(buffer.delete_backward(), None);
+        buffer.move_to(buffer.len());
+        assert_eq!(buffer.delete_forward(), None);
+        assert!(buffer.iter().copied().eq((0..100).filter(|&i| i != 50)));
+    }
+
+    #[test]
+    fn test_move_across_large_gap() {
+        // ギャップが大きくても、1つずつの移動はギャップ全体を動かさない
+        let mut buffer = GapBuffer::with_capacity(1 << 20);
+        for i in 0..10 {
+            buffer.insert(i);
+        }
+        buffer.move_to(5);
+        for _ in 0..10_000 {
+            assert!(buffer.move_left());
+            assert!(buffer.move_right());
+        }
+        buffer.move_to(0);
+        buffer.move_to(10);
+        buffer.move_to(3);
+        buffer.insert(-1);
+        assert_eq!(buffer.len(), 11);
+        assert_eq!(buffer.get(3), Some(&-1));
+        assert!(buffer
+            .iter()
+            .copied()
+            .eq([0, 1, 2, -1, 3, 4, 5, 6, 7, 8, 9]));
+    }
+
+    #[test]
+    fn test_lines_and_cursor_movement() {
+        let mut text = GapBuffer::from_text("うさぎ\nはねる\n\nおわり");
+        assert_eq!(text.line_count(), 4);
+        assert_eq!(text.line(1).as_deref(), Some("はねる"));
+        assert_eq!(text.line(2).as_deref(), Some(""));
+        assert_eq!(text.line(4), None);
+        assert_eq!(text.line_col(5), (1, 1));
+        assert_eq!(text.position(1, 1), Some(5));
+        assert_eq!(text.position(1, 4), None);
+
+        assert!(text.move_to_line_col(0, 2));
+        assert!(text.move_down());
+        assert_eq!(text.line_col(text.cursor()), (1, 2));
+        // 空行では行末（列0）へ動く
+        assert!(text.move_down());
+        assert_eq!(text.line_col(text.cursor()), (2, 0));
+        assert!(text.move_down());
+        assert!(!text.move_down());
+        assert!(text.move_up());
+        assert!(text.move_up());
+        assert_eq!(text.line_col(text.cursor()), (1, 0));
+
+        text.insert_str("ぴょん");
+        assert_eq!(text.to_string(), "うさぎ\nぴょんはねる\n\nおわり");
+    }
+
+    #[test]
+    fn test_move_up_and_down_at_edges() {
+        // 末尾が改行なら、最後の行は空行
+        let mut text = GapBuffer::from_text("ab\nc\n");
+        assert_eq!(text.line_col(text.cursor()), (2, 0));
+        assert!(!text.move_down());
+        assert!(text.move_up());
+        assert_eq!(text.line_col(text.cursor()), (1, 0));
+        text.move_to(2);
+        assert!(text.move_down());
+        assert_eq!(text.line_col(text.cursor()), (1, 1));
+        assert!(text.move_up());
+        assert_eq!(text.cursor(), 1);
+        assert!(!text.move_up());
+    }
+}
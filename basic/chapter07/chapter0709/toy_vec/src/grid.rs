@@ -0,0 +1,454 @@
+use crate::codec::{Decode, DecodeError, Decoder, Encode, Encoder};
+use crate::ToyVec;
+use std::fmt;
+use std::mem;
+use std::ops::{Index, IndexMut};
+
+// 上下左右の隣接セルへの(行, 列)の差分
+const NEIGHBORS_4: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+// 斜めも含めた隣接セルへの差分
+const NEIGHBORS_8: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+// 2次元のグリッド
+// すべてのセルを1つの`ToyVec<T>`に行優先（row-major）で並べるので、
+// `ToyVec<ToyVec<T>>`と違って行の長さがそろい、同じ行のセルはメモリ上で隣り合う
+pub struct Grid<T> {
+    // `(row, col)`のセルは`cells[row * cols + col]`にある
+    cells: ToyVec<T>,
+    rows: usize,
+    cols: usize,
+}
+
+impl<T: Default> Grid<T> {
+    // すべてのセルが`T::default()`のグリッドを作る
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Self::from_fn(rows, cols, |_, _| T::default())
+    }
+
+    // 各セルの値を`f(row, col)`で決めてグリッドを作る
+    pub fn from_fn<F>(rows: usize, cols: usize, mut f: F) -> Self
+    where
+        F: FnMut(usize, usize) -> T,
+    {
+        let len = rows.checked_mul(cols).expect("grid size overflow");
+        let mut cells = ToyVec::with_capacity(len);
+        for row in 0..rows {
+            for col in 0..cols {
+                cells.push(f(row, col));
+            }
+        }
+        Self { cells, rows, cols }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+        if row < self.rows && col < self.cols {
+            self.cells.get(row * self.cols + col)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, row: usize, col: usize) -> Option<&mut T> {
+        if row < self.rows && col < self.cols {
+            self.cells.get_mut(row * self.cols + col)
+        } else {
+            None
+        }
+    }
+
+    // `row`行目のセルをスライスで返す
+    pub fn row(&self, row: usize) -> Option<&[T]> {
+        if row < self.rows {
+            Some(&self.cells.as_slice()[row * self.cols..(row + 1) * self.cols])
+        } else {
+            None
+        }
+    }
+
+    // 各行をスライスで返すイテレータを作る
+    pub fn iter_rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.rows).filter_map(move |row| self.row(row))
+    }
+
+    // `col`列目のセルを上から順に返すイテレータを作る
+    pub fn col(&self, col: usize) -> Option<impl Iterator<Item = &T>> {
+        if col < self.cols {
+            // 0行のグリッドでは`cells`が空なので、`col`から始まるスライスは取れない
+            let cells = self.cells.as_slice().get(col..).unwrap_or(&[]);
+            Some(cells.iter().step_by(self.cols))
+        } else {
+            None
+        }
+    }
+
+    // `(row, col)`を左上とする`rows`行`cols`列の部分グリッドを返す。はみ出すなら`None`
+    pub fn view(
+        &self,
+        row: usize,
+        col: usize,
+        rows: usize,
+        cols: usize,
+    ) -> Option<GridView<'_, T>> {
+        let fits = |start: usize, len: usize, max: usize| {
+            start.checked_add(len).is_some_and(|end| end <= max)
+        };
+        if fits(row, rows, self.rows) && fits(col, cols, self.cols) {
+            Some(GridView {
+                grid: self,
+                row,
+                col,
+                rows,
+                cols,
+            })
+        } else {
+            None
+        }
+    }
+
+    // 行と列を入れ替えたグリッドを返す
+    pub fn transpose(&self) -> Self
+    where
+        T: Clone,
+    {
+        Self::from_fn(self.cols, self.rows, |row, col| self[(col, row)].clone())
+    }
+
+    // `index`行目に行を挿入する。行の長さは列数と同じでなければならない
+    // 0行0列のグリッドに挿入した場合だけは、その行の長さが列数になる
+    pub fn insert_row<I>(&mut self, index: usize, row: I)
+    where
+        I: IntoIterator<Item = T>,
+    {
+        assert!(
+            index <= self.rows,
+            "row index (is {}) should be <= rows (is {})",
+            index,
+            self.rows
+        );
+        let mut new_row = ToyVec::new();
+        for cell in row {
+            new_row.push(cell);
+        }
+        if self.rows == 0 && self.cols == 0 {
+            self.cols = new_row.len();
+        }
+        assert_eq!(
+            new_row.len(),
+            self.cols,
+            "row length must be equal to the number of columns"
+        );
+        // 挿入位置より前の行、新しい行、後ろの行の順に並べた領域を作り直す（O(n)）
+        let old = mem::replace(
+            &mut self.cells,
+            ToyVec::with_capacity((self.rows + 1) * self.cols),
+        );
+        let mut old = old.into_iter();
+        for cell in old.by_ref().take(index * self.cols) {
+            self.cells.push(cell);
+        }
+        for cell in new_row.into_iter().chain(old) {
+            self.cells.push(cell);
+        }
+        self.rows += 1;
+    }
+
+    // `index`列目を取り除き、その列のセルを上から順に返す
+    pub fn remove_col(&mut self, index: usize) -> ToyVec<T> {
+        assert!(
+            index < self.cols,
+            "column index (is {}) should be < cols (is {})",
+            index,
+            self.cols
+        );
+        let mut removed = ToyVec::with_capacity(self.rows);
+        // 取り除く列とそれ以外のセルを1回の走査で振り分け、領域を作り直す（O(n)）
+        let old = mem::replace(
+            &mut self.cells,
+            ToyVec::with_capacity(self.rows * (self.cols - 1)),
+        );
+        for (i, cell) in old.into_iter().enumerate() {
+            if i % self.cols == index {
+                removed.push(cell);
+            } else {
+                self.cells.push(cell);
+            }
+        }
+        self.cols -= 1;
+        removed
+    }
+
+    // 上下左右で隣り合うセルの位置を返す
+    pub fn neighbors4(&self, row: usize, col: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.neighbors(row, col, &NEIGHBORS_4)
+    }
+
+    // 斜めも含めて隣り合うセルの位置を返す
+    pub fn neighbors8(&self, row: usize, col: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.neighbors(row, col, &NEIGHBORS_8)
+    }
+
+    fn neighbors(
+        &self,
+        row: usize,
+        col: usize,
+        offsets: &'static [(isize, isize)],
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        offsets.iter().filter_map(move |&(dr, dc)| {
+            let r = row.checked_add_signed(dr)?;
+            let c = col.checked_add_signed(dc)?;
+            if r < self.rows && c < self.cols {
+                Some((r, c))
+            } else {
+                None
+            }
+        })
+    }
+}
+
+impl<T: Default> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (row, col): (usize, usize)) -> &T {
+        let (rows, cols) = (self.rows, self.cols);
+        self.get(row, col).unwrap_or_else(|| {
+            panic!(
+                "index out of bounds: the size is {}x{} but the index is ({}, {})",
+                rows, cols, row, col
+            )
+        })
+    }
+}
+
+impl<T: Default> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut T {
+        let (rows, cols) = (self.rows, self.cols);
+        self.get_mut(row, col).unwrap_or_else(|| {
+            panic!(
+                "index out of bounds: the size is {}x{} but the index is ({}, {})",
+                rows, cols, row, col
+            )
+        })
+    }
+}
+
+// 各セルを列ごとに幅をそろえて右寄せし、1行ずつ出力する
+impl<T: Default + fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let texts: Vec<String> = self.cells.iter().map(|cell| cell.to_string()).collect();
+        let width = texts.iter().map(|s| s.chars().count()).max().unwrap_or(0);
+        for (row, line) in texts.chunks(self.cols.max(1)).enumerate() {
+            if row > 0 {
+                writeln!(f)?;
+            }
+            for (col, text) in line.iter().enumerate() {
+                if col > 0 {
+                    write!(f, " ")?;
+                }
+                write!(f, "{:>width$}", text, width = width)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// バイナリ形式では行数、列数、行優先に並べたセルの順に書く
+impl<T: Encode + Default> Encode for Grid<T> {
+    fn encode(&self, enc: &mut Encoder) {
+        self.rows.encode(enc);
+        self.cols.encode(enc);
+        enc.write_seq(self.cells.len(), self.cells.iter());
+    }
+}
+
+impl<T: Decode + Default> Decode for Grid<T> {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let rows = usize::decode(dec)?;
+        let cols = usize::decode(dec)?;
+        let cells = dec.read_seq()?;
+        if rows.checked_mul(cols) != Some(cells.len()) {
+            return Err(DecodeError::Inconsistent(
+                "number of cells does not match the grid size",
+            ));
+        }
+        Ok(Self { cells, rows, cols })
+    }
+}
+
+// グリッドの一部を切り出した読み取り専用のビュー
+pub struct GridView<'grid, T> {
+    grid: &'grid Grid<T>,
+    row: usize,
+    col: usize,
+    rows: usize,
+    cols: usize,
+}
+
+impl<'grid, T: Default> GridView<'grid, T> {
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    // ビューの左上を`(0, 0)`とした位置のセルを返す
+    pub fn get(&self, row: usize, col: usize) -> Option<&'grid T> {
+        if row < self.rows && col < self.cols {
+            self.grid.get(self.row + row, self.col + col)
+        } else {
+            None
+        }
+    }
+
+    pub fn row(&self, row: usize) -> Option<&'grid [T]> {
+        if row < self.rows {
+            let full = self.grid.row(self.row + row)?;
+            Some(&full[self.col..self.col + self.cols])
+        } else {
+            None
+        }
+    }
+
+    pub fn iter_rows(&self) -> impl Iterator<Item = &'grid [T]> + '_ {
+        (0..self.rows).filter_map(move |row| self.row(row))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Grid;
+    use crate::codec::{from_bytes, to_bytes, DecodeError, Encoder};
+
+    #[test]
+    fn test_index_rows_and_cols() {
+        let mut grid = Grid::from_fn(3, 4, |r, c| r * 10 + c);
+        assert_eq!(grid[(2, 3)], 23);
+        grid[(1, 1)] = 99;
+        assert_eq!(grid.get(1, 1), Some(&99));
+        assert_eq!(grid.get(3, 0), None);
+        assert_eq!(grid.get(0, 4), None);
+
+        assert_eq!(grid.row(2), Some(&[20, 21, 22, 23][..]));
+        assert!(grid.col(1).unwrap().eq(&[1, 99, 21]));
+        assert!(grid.col(4).is_none());
+        assert_eq!(grid.iter_rows().count(), 3);
+    }
+
+    #[test]
+    fn test_grid_without_rows() {
+        let grid = Grid::<u8>::new(0, 5);
+        assert_eq!(grid.col(1).unwrap().count(), 0);
+        assert_eq!(grid.col(4).unwrap().count(), 0);
+        assert!(grid.col(5).is_none());
+        assert_eq!(grid.row(0), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "grid size overflow")]
+    fn test_from_fn_size_overflow_panics() {
+        Grid::from_fn(usize::MAX, 2, |_, _| 0u8);
+    }
+
+    #[test]
+    fn test_view_and_transpose() {
+        let grid = Grid::from_fn(4, 4, |r, c| r * 4 + c);
+        let view = grid.view(1, 2, 2, 2).unwrap();
+        assert_eq!(view.get(0, 0), Some(&6));
+        assert_eq!(view.get(1, 1), Some(&11));
+        assert_eq!(view.get(2, 0), None);
+        assert!(view.iter_rows().eq(vec![&[6, 7][..], &[10, 11][..]]));
+        assert!(grid.view(3, 3, 2, 1).is_none());
+        assert!(grid.view(1, 0, usize::MAX, 1).is_none());
+        assert!(grid.view(0, 1, 1, usize::MAX).is_none());
+
+        let t = Grid::from_fn(2, 3, |r, c| r * 3 + c).transpose();
+        assert_eq!((t.rows(), t.cols()), (3, 2));
+        assert_eq!(t.row(2), Some(&[2, 5][..]));
+    }
+
+    #[test]
+    fn test_insert_row_and_remove_col() {
+        let mut grid = Grid::new(0, 0);
+        grid.insert_row(0, vec![1, 2, 3]);
+        grid.insert_row(1, vec![7, 8, 9]);
+        grid.insert_row(1, vec![4, 5, 6]);
+        assert_eq!(grid.row(1), Some(&[4, 5, 6][..]));
+
+        let removed = grid.remove_col(1);
+        assert_eq!(removed.as_slice(), &[2, 5, 8]);
+        assert_eq!(grid.cols(), 2);
+        assert!(grid
+            .iter_rows()
+            .eq(vec![&[1, 3][..], &[4, 6][..], &[7, 9][..]]));
+    }
+
+    #[test]
+    #[should_panic(expected = "row length")]
+    fn test_insert_ragged_row_panics() {
+        let mut grid = Grid::<u8>::new(2, 3);
+        grid.insert_row(0, vec![1, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "row length")]
+    fn test_insert_row_into_empty_grid_with_cols_panics() {
+        // 行がなくても列数が決まっていれば、それと違う長さの行は挿入できない
+        let mut grid = Grid::<u8>::new(0, 5);
+        grid.insert_row(0, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_codec() {
+        let grid = Grid::from_fn(2, 3, |r, c| (r * 3 + c) as i32 - 2);
+        let decoded = from_bytes::<Grid<i32>>(to_bytes(&grid).as_slice()).unwrap();
+        assert_eq!((decoded.rows(), decoded.cols()), (2, 3));
+        assert!(decoded.iter_rows().eq(grid.iter_rows()));
+
+        // 行数と列数がセルの数と合わない
+        let mut enc = Encoder::new();
+        enc.write_varint(2);
+        enc.write_varint(2);
+        enc.write_seq(3, &[0u8, 0, 0]);
+        assert!(matches!(
+            from_bytes::<Grid<u8>>(enc.finish().as_slice()),
+            Err(DecodeError::Inconsistent(_))
+        ));
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let grid = Grid::<u8>::new(3, 3);
+        assert_eq!(grid.neighbors4(1, 1).count(), 4);
+        assert_eq!(grid.neighbors8(1, 1).count(), 8);
+        assert_eq!(
+            grid.neighbors4(0, 0).collect::<Vec<_>>(),
+            vec![(0, 1), (1, 0)]
+        );
+        assert_eq!(grid.neighbors8(2, 2).count(), 3);
+    }
+
+    #[test]
+    fn test_display() {
+        let grid = Grid::from_fn(2, 3, |r, c| (r * 3 + c) * 5);
+        assert_eq!(grid.to_string(), " 0  5 10\n15 20 25");
+        assert_eq!(Grid::<u8>::new(0, 0).to_string(), "");
+    }
+}
@@ -0,0 +1,191 @@
+use crate::ToyVec;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+// インターンした文字列を表すID。`u32`1つ分なので、`String`や`&str`よりずっと小さい
+// 同じインターナから得たシンボル同士なら、文字列を比較する代わりに整数の比較で済む
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    pub fn as_u32(self) -> u32 {
+        self.0
+    }
+}
+
+// 文字列をシンボルに対応付けるインターナ
+// すべての文字列のバイト列を1つの`ToyVec<u8>`に連続して格納するので、
+// 文字列ごとにヒープ領域を確保しなくて済む
+pub struct ToyInterner {
+    // インターンした文字列のバイト列を連結したもの
+    buffer: ToyVec<u8>,
+    // `ends[i]`はシンボル`i`の文字列の`buffer`内での終端。始端は`ends[i - 1]`（`i == 0`なら0）
+    ends: ToyVec<usize>,
+    // 文字列からシンボルを引くためのオープンアドレス法のハッシュテーブル
+    // 各要素は「シンボル + 1」で、0は空きを表す。キャパシティは常に2のべき乗
+    table: ToyVec<u32>,
+}
+
+impl ToyInterner {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    // `capacity`個の文字列をテーブルの拡張なしでインターンできるインターナを作る
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut interner = Self {
+            buffer: ToyVec::new(),
+            ends: ToyVec::with_capacity(capacity),
+            table: ToyVec::new(),
+        };
+        interner.resize_table((capacity * 2).next_power_of_two().max(8));
+        interner
+    }
+
+    // インターンした文字列の数を返す
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    // 文字列をインターンしてシンボルを返す。すでにインターン済みなら同じシンボルを返す
+    pub fn intern(&mut self, s: &str) -> Symbol {
+        let slot = self.find_slot(s);
+        if let Some(&entry) = self.table.get(slot) {
+            if entry != 0 {
+                return Symbol(entry - 1);
+            }
+        }
+
+        let index = self.len();
+        assert!(index < u32::MAX as usize, "too many interned strings");
+        for &b in s.as_bytes() {
+            self.buffer.push(b);
+        }
+        self.ends.push(self.buffer.len());
+        let symbol = Symbol(index as u32);
+        if let Some(entry) = self.table.get_mut(slot) {
+            *entry = symbol.0 + 1;
+        }
+
+        // 負荷率が1/2を超えたらテーブルを拡張する
+        if self.len() * 2 > self.table.len() {
+            self.resize_table(self.table.len() * 2);
+        }
+        symbol
+    }
+
+    // インターン済みならシンボルを返す。インターンはしない
+    pub fn get(&self, s: &str) -> Option<Symbol> {
+        match self.table.get(self.find_slot(s)) {
+            Some(&entry) if entry != 0 => Some(Symbol(entry - 1)),
+            _ => None,
+        }
+    }
+
+    // シンボルを文字列に戻す。O(1)で済む
+    pub fn resolve(&self, symbol: Symbol) -> Option<&str> {
+        let index = symbol.0 as usize;
+        let end = *self.ends.get(index)?;
+        let start = if index == 0 {
+            0
+        } else {
+            self.ends.as_slice()[index - 1]
+        };
+        let bytes = &self.buffer.as_slice()[start..end];
+        // `buffer`には`&str`のバイト列しか格納しないので、UTF-8として正しい
+        Some(unsafe { std::str::from_utf8_unchecked(bytes) })
+    }
+
+    // (シンボル, 文字列)をインターンした順に返すイテレータを作る
+    pub fn iter(&self) -> impl Iterator<Item = (Symbol, &str)> + '_ {
+        (0..self.len()).map(move |i| {
+            let symbol = Symbol(i as u32);
+            (symbol, self.resolve(symbol).unwrap_or_default())
+        })
+    }
+
+    // 文字列`s`が入っている、または入るべきテーブルの位置を線形探索で求める
+    fn find_slot(&self, s: &str) -> usize {
+        let mask = self.table.len() - 1;
+        let mut slot = (hash(s) as usize) & mask;
+        loop {
+            match self.table.get(slot) {
+                Some(&entry) if entry != 0 => {
+                    if self.resolve(Symbol(entry - 1)) == Some(s) {
+                        return slot;
+                    }
+                }
+                _ => return slot,
+            }
+            slot = (slot + 1) & mask;
+        }
+    }
+
+    // テーブルを作り直し、インターン済みの全シンボルを入れ直す
+    fn resize_table(&mut self, capacity: usize) {
+        let mut table = ToyVec::with_capacity(capacity);
+        for _ in 0..capacity {
+            table.push(0);
+        }
+        self.table = table;
+        for i in 0..self.len() {
+            let symbol = Symbol(i as u32);
+            let slot = self.find_slot(self.resolve(symbol).unwrap_or_default());
+            if let Some(entry) = self.table.get_mut(slot) {
+                *entry = symbol.0 + 1;
+            }
+        }
+    }
+}
+
+impl Default for ToyInterner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn hash(s: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    s.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ToyInterner;
+
+    #[test]
+    fn test_intern_and_resolve() {
+        let mut interner = ToyInterner::new();
+        let lop = interner.intern("ロップイヤー");
+        let dutch = interner.intern("ダッチ");
+
+        // 同じ文字列は同じシンボルになる
+        assert_eq!(interner.intern("ロップイヤー"), lop);
+        assert_ne!(lop, dutch);
+        assert_eq!(interner.len(), 2);
+
+        assert_eq!(interner.resolve(lop), Some("ロップイヤー"));
+        assert_eq!(interner.resolve(dutch), Some("ダッチ"));
+        assert_eq!(interner.get("ダッチ"), Some(dutch));
+        assert_eq!(interner.get("ドワーフホト"), None);
+    }
+
+    #[test]
+    fn test_many_strings() {
+        let mut interner = ToyInterner::with_capacity(2);
+        let symbols: Vec<_> = (0..1000).map(|i| interner.intern(&i.to_string())).collect();
+        // テーブルを拡張してもシンボルは変わらない
+        for (i, &symbol) in symbols.iter().enumerate() {
+            assert_eq!(interner.get(&i.to_string()), Some(symbol));
+            assert_eq!(interner.resolve(symbol), Some(i.to_string().as_str()));
+        }
+        let empty = interner.intern("");
+        assert_eq!(interner.resolve(empty), Some(""));
+        assert_eq!(interner.iter().count(), 1001);
+    }
+}
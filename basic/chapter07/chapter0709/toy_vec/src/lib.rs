@@ -1,5 +1,54 @@
 use std::fmt;
 
+pub mod adapters;
+pub mod arena;
+pub mod bounded;
+pub mod chunks;
+pub mod codec;
+pub mod compact;
+pub mod concurrent;
+pub mod cursor;
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod gap_buffer;
+pub mod grid;
+pub mod interner;
+pub mod mmap;
+pub mod observable;
+pub mod packed;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod persistent;
+pub mod rope;
+pub mod scheduler;
+pub mod shared;
+#[cfg(feature = "serde")]
+mod serde_impl;
+pub mod slot_map;
+pub mod sparse;
+pub mod versioned;
+
+pub use adapters::{ToyQueue, ToyStack};
+pub use arena::ToyArena;
+pub use bounded::{BoundedToyVec, OverflowPolicy};
+pub use compact::CompactToyVec;
+pub use concurrent::ConcurrentToyVec;
+pub use error::{ToyVecError, TryPushError};
+pub use gap_buffer::GapBuffer;
+pub use grid::Grid;
+pub use interner::ToyInterner;
+pub use mmap::MmapToyVec;
+pub use observable::ObservableToyVec;
+pub use packed::PackedToyVec;
+pub use persistent::{PersistentToyVec, TransientToyVec};
+pub use rope::ToyRope;
+pub use scheduler::WorkScheduler;
+pub use shared::{ArcToyVec, RcToyVec};
+pub use slot_map::{DenseSlotMap, ToySlotMap};
+pub use sparse::SparseToyVec;
+pub use versioned::VersionedToyVec;
+
 pub struct ToyVec<T> {
     // `T`型の要素を格納する領域。各要素はヒープ領域に置かれる
     elements: Box<[T]>,
@@ -38,6 +87,11 @@ impl<T: Default> ToyVec<T> {
         self.len
     }
 
+    // 要素が1つもなければ`true`を返す
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
     // ベクタの現在のキャパシティを返す
     pub fn capacity(&self) -> usize {
         // `elements`の要素数が`ToyVec`のキャパシティになる
@@ -68,6 +122,15 @@ impl<T: Default> ToyVec<T> {
         }
     }
 
+    // `get`の可変版。インデックスが範囲内なら要素への可変の参照を返す
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index < self.len {
+            Some(&mut self.elements[index])
+        } else {
+            None
+        }
+    }
+
     // インデックスが範囲内なら要素への参照を返し、さもなければdefaultで与えた別の値への参照を返す
     pub fn get_or<'a>(&'a self, index: usize, default: &'a T) -> &'a T {
         self.get(index).unwrap_or(default)
@@ -80,14 +143,57 @@ impl<T: Default> ToyVec<T> {
             // 要素を1つ削除する
             self.len -= 1;
 
-            // 第二引数で第一引数の要素を置き換え、置き換える前の値を返す
-            // `T`型のデフォルト値を与え、もし`T`型が`String`型なら、デフォルト値は空の文字列になる
-            let elem = std::mem::replace(&mut self.elements[self.len], Default::default());
+            // 要素を`T`型のデフォルト値と置き換え、置き換える前の値を返す
+            // もし`T`型が`String`型なら、デフォルト値は空の文字列になる
+            let elem = std::mem::take(&mut self.elements[self.len]);
             // `elem`を`Some`でラップする
             Some(elem)
         }
     }
 
+    // `index`の位置に要素を挿入する。それ以降の要素は1つずつ後ろへずれる
+    pub fn insert(&mut self, index: usize, element: T) {
+        assert!(index <= self.len, "insertion index (is {}) should be <= len (is {})", index, self.len);
+        if self.len == self.capacity() {
+            self.grow();
+        }
+        // いったん末尾に置いてから、`index`以降を1つ右へ回転させる
+        self.elements[self.len] = element;
+        self.elements[index..=self.len].rotate_right(1);
+        self.len += 1;
+    }
+
+    // `index`の要素を取り除いて返す。それ以降の要素は1つずつ前へずれる
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+        // 取り除く要素を末尾まで回転させてから`pop`する
+        self.elements[index..self.len].rotate_left(1);
+        self.pop()
+    }
+
+    // `index`の要素を取り除いて返す。空いた位置には最後の要素を移すので順序は保たれないが、O(1)で済む
+    pub fn swap_remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+        let last = self.len - 1;
+        // 取り除く要素を末尾と入れ替えてから`pop`する
+        self.elements.swap(index, last);
+        self.pop()
+    }
+
+    // 格納済みの要素をスライスとして返す（余っている領域は含まない）
+    pub fn as_slice(&self) -> &[T] {
+        &self.elements[..self.len]
+    }
+
+    // `as_slice`の可変版
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.elements[..self.len]
+    }
+
     // elementsを拡張する（より大きなサイズで作り直す）
     pub fn grow(&mut self) {
         // もし、現在の`elements`が空なら
@@ -95,8 +201,9 @@ impl<T: Default> ToyVec<T> {
             // 1要素分の領域を確保する
             self.elements = Self::allocate_in_heap(1);
         } else {
-            // 現在の2倍の領域を確保する
-            let new_elements = Self::allocate_in_heap(self.capacity() * 2);
+            // 現在の2倍の領域を確保する。2倍が`usize`に収まらなければパニックする（`try_push`はエラーを返す）
+            let capacity = self.capacity().checked_mul(2).expect("capacity overflow");
+            let new_elements = Self::allocate_in_heap(capacity);
             // `self.elements`を置き換える
             // `std::mem::replace`は、第二引数の要素で第一引数の要素を置き換える
             let old_elements = std::mem::replace(&mut self.elements, new_elements);
@@ -108,6 +215,37 @@ impl<T: Default> ToyVec<T> {
         }
     }
 
+    // 少なくとも`additional`個の要素を追加できるようにする。足りなければ必要な分だけの領域を確保し直す
+    pub fn reserve(&mut self, additional: usize) {
+        let required = self
+            .len
+            .checked_add(additional)
+            .expect("capacity overflow");
+        if required > self.capacity() {
+            self.reallocate(required);
+        }
+    }
+
+    // キャパシティを要素数まで縮める
+    pub fn shrink_to_fit(&mut self) {
+        if self.capacity() > self.len {
+            self.reallocate(self.len);
+        }
+    }
+
+    // `capacity`個の要素が入る領域を確保し、格納済みの要素をムーブする
+    fn reallocate(&mut self, capacity: usize) {
+        self.move_into(Self::allocate_in_heap(capacity));
+    }
+
+    // 格納済みの要素を`new_elements`へムーブし、`elements`を置き換える
+    fn move_into(&mut self, new_elements: Box<[T]>) {
+        let old_elements = std::mem::replace(&mut self.elements, new_elements);
+        for (i, elem) in old_elements.into_vec().into_iter().take(self.len).enumerate() {
+            self.elements[i] = elem;
+        }
+    }
+
     pub fn iter<'vec>(&'vec self) -> Iter<'vec, T> {
         // Iter構造体の定義より、ライフタイムは'vecになる
         Iter {
@@ -125,15 +263,6 @@ impl<T: Default> ToyVec<T> {
             pos: 0,
         }
     }
-
-    // 要素の所有権をとる（Option<T>）イテレータを作る
-    pub fn into_iter<'vec>(self) -> IntoIter<T> {
-        IntoIter {
-            elements: self.elements,
-            len: self.len,
-            pos: 0,
-        }
-    }
 }
 
 // `ToyVec`に`Default`トレイトを実装
@@ -199,8 +328,13 @@ impl<T: Default> IntoIterator for ToyVec<T> {
     type IntoIter = IntoIter<T>;
 
     // selfの型はToyVec<T>
+    // 要素の所有権をとる（Option<T>）イテレータを作る
     fn into_iter(self) -> Self::IntoIter {
-        self.into_iter()
+        IntoIter {
+            elements: self.elements,
+            len: self.len,
+            pos: 0,
+        }
     }
 }
 
@@ -213,7 +347,7 @@ impl<T: Default> IntoIterator for ToyVec<T> {
 // 生存してる間は、ToyVec<T>は変更できない
 pub struct Iter<'vec, T> {
     // `ToyVec`構造体の`elements`を指す不変の参照
-    elements: &'vec Box<[T]>,
+    elements: &'vec [T],
     // `ToyVec`の長さ
     len: usize,
     // 次に返す要素のインデックス
@@ -298,7 +432,7 @@ impl<T: Default> Iterator for IntoIter<T> {
             None
         } else {
             // `&mut self`から要素`T`をムーブできないので、`replace`でデフォルト値と交換
-            let elem = std::mem::replace(&mut self.elements[self.pos], Default::default());
+            let elem = std::mem::take(&mut self.elements[self.pos]);
             self.pos += 1;
             Some(elem)
         }
@@ -339,6 +473,8 @@ mod tests {
     }
 
     #[test]
+    // `Option::map`で要素を書き換える書き方をそのまま示すため、clippyの指摘は抑える
+    #[allow(clippy::option_map_unit_fn)]
     fn test_iter_mut() {
         let mut v = ToyVec::new();
         v.push(1);
@@ -391,4 +527,46 @@ mod tests {
 
         assert_eq!(sum, [1, 1, 2, 3, 5].iter().sum());
     }
+
+    #[test]
+    fn test_insert_remove() {
+        let mut v = ToyVec::new();
+        v.push(1);
+        v.push(3);
+        v.insert(1, 2);
+        v.insert(0, 0);
+        v.insert(4, 4);
+        assert_eq!(v.as_slice(), &[0, 1, 2, 3, 4]);
+
+        assert_eq!(v.remove(0), Some(0));
+        assert_eq!(v.remove(2), Some(3));
+        assert_eq!(v.remove(3), None);
+        assert_eq!(v.as_slice(), &[1, 2, 4]);
+
+        assert_eq!(v.swap_remove(0), Some(1));
+        assert_eq!(v.as_slice(), &[4, 2]);
+    }
+
+    #[test]
+    fn test_reserve_shrink() {
+        let mut v = ToyVec::new();
+        v.push(1);
+        v.push(2);
+        v.reserve(10);
+        assert_eq!(v.capacity(), 12);
+        // 足りていれば確保し直さない
+        v.reserve(5);
+        assert_eq!(v.capacity(), 12);
+        v.shrink_to_fit();
+        assert_eq!(v.capacity(), 2);
+        assert_eq!(v.as_slice(), &[1, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn test_reserve_overflow_panics() {
+        let mut v = ToyVec::new();
+        v.push(1);
+        v.reserve(usize::MAX);
+    }
 }
\ No newline at end of file
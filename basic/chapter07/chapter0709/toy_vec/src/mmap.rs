@@ -0,0 +1,425 @@
+use memmap2::{Mmap, MmapMut};
+use std::convert::TryInto;
+use std::error::Error;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::marker::PhantomData;
+use std::mem;
+use std::path::Path;
+
+// ファイルの先頭に置くヘッダ（リトルエンディアン）
+//   0..8   マジックナンバー
+//   8..12  フォーマットのバージョン
+//   12..16 要素1つのバイト数
+//   16..24 要素数
+//   24..32 予約（0）
+// 要素はヘッダの直後から隙間なく並べる
+const MAGIC: [u8; 8] = *b"TOYVEC\0\0";
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 32;
+// ファイルを作ったときに確保しておく要素数
+const INITIAL_CAPACITY: usize = 16;
+
+/// ファイルのバイト列をそのまま値として読める型（plain old data）
+///
+/// # Safety
+///
+/// どんなビット列も正しい値であり、パディングやポインタを含まない型にだけ実装してよい
+pub unsafe trait Pod: Copy + 'static {}
+
+unsafe impl Pod for u8 {}
+unsafe impl Pod for u16 {}
+unsafe impl Pod for u32 {}
+unsafe impl Pod for u64 {}
+unsafe impl Pod for i8 {}
+unsafe impl Pod for i16 {}
+unsafe impl Pod for i32 {}
+unsafe impl Pod for i64 {}
+unsafe impl Pod for f32 {}
+unsafe impl Pod for f64 {}
+
+// ファイルを開くとき、またはファイルへ書き込むときのエラー
+#[derive(Debug)]
+pub enum MmapError {
+    Io(io::Error),
+    // ファイルがヘッダより短い
+    TooShort { file_len: u64 },
+    BadMagic,
+    UnsupportedVersion(u32),
+    // ファイルの要素のバイト数が`T`と違う
+    ElementSizeMismatch { expected: usize, found: usize },
+    // ヘッダの要素数がファイルの大きさに収まらない
+    LengthOutOfRange { len: u64, capacity: usize },
+    // 読み取り専用で開いたファイルに書き込もうとした
+    ReadOnly,
+}
+
+impl fmt::Display for MmapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MmapError::Io(e) => write!(f, "I/O error: {}", e),
+            MmapError::TooShort { file_len } => write!(
+                f,
+                "file is {} bytes long, shorter than the {}-byte header",
+                file_len, HEADER_LEN
+            ),
+            MmapError::BadMagic => write!(f, "not a ToyVec file (bad magic number)"),
+            MmapError::UnsupportedVersion(v) => {
+                write!(f, "unsupported format version {} (expected {})", v, VERSION)
+            }
+            MmapError::ElementSizeMismatch { expected, found } => write!(
+                f,
+                "element size mismatch: file has {}-byte elements, expected {}",
+                found, expected
+            ),
+            MmapError::LengthOutOfRange { len, capacity } => write!(
+                f,
+                "header says {} elements but the file only holds {}",
+                len, capacity
+            ),
+            MmapError::ReadOnly => write!(f, "vector was opened read-only"),
+        }
+    }
+}
+
+impl Error for MmapError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MmapError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for MmapError {
+    fn from(e: io::Error) -> Self {
+        MmapError::Io(e)
+    }
+}
+
+enum Map {
+    ReadWrite(MmapMut),
+    ReadOnly(Mmap),
+}
+
+impl Map {
+    fn bytes(&self) -> &[u8] {
+        match self {
+            Map::ReadWrite(m) => m,
+            Map::ReadOnly(m) => m,
+        }
+    }
+}
+
+// ローカルディスク上のファイルを記憶領域とする`ToyVec`
+// ファイルをメモリにマップするので、大きなファイルでも開くときに全体を読み込む必要がない
+pub struct MmapToyVec<T: Pod> {
+    file: File,
+    map: Map,
+    len: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod> MmapToyVec<T> {
+    // 新しいファイルを作る。同じ名前のファイルがあれば中身を捨てる
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, MmapError> {
+        Self::check_layout();
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len(file_len::<T>(INITIAL_CAPACITY))?;
+        let map = unsafe { MmapMut::map_mut(&file)? };
+        let mut vec = Self {
+            file,
+            map: Map::ReadWrite(map),
+            len: 0,
+            _marker: PhantomData,
+        };
+        vec.write_header()?;
+        Ok(vec)
+    }
+
+    // 既存のファイルを読み書きできるように開く
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, MmapError> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let map = unsafe { MmapMut::map_mut(&file)? };
+        Self::from_map(file, Map::ReadWrite(map))
+    }
+
+    // 既存のファイルを読み取り専用で開く。書き込もうとすると`MmapError::ReadOnly`を返す
+    pub fn open_read_only<P: AsRef<Path>>(path: P) -> Result<Self, MmapError> {
+        let file = File::open(path)?;
+        let map = unsafe { Mmap::map(&file)? };
+        Self::from_map(file, Map::ReadOnly(map))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // ファイルを伸ばさずに格納できる要素数
+    pub fn capacity(&self) -> usize {
+        (self.map.bytes().len() - HEADER_LEN) / mem::size_of::<T>()
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        matches!(self.map, Map::ReadOnly(_))
+    }
+
+    pub fn get(&self, index: usize) -> Option<T> {
+        self.as_slice().get(index).copied()
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        let data = self.map.bytes()[HEADER_LEN..].as_ptr() as *const T;
+        // マップの先頭はページ境界に揃っており、要素はそこから`HEADER_LEN`バイト目に並んでいる
+        // `T`は`Pod`なので、どんなバイト列でも正しい値として読める
+        unsafe { std::slice::from_raw_parts(data, self.len) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> Result<&mut [T], MmapError> {
+        let len = self.len;
+        let map = self.map_mut()?;
+        let data = map[HEADER_LEN..].as_mut_ptr() as *mut T;
+        Ok(unsafe { std::slice::from_raw_parts_mut(data, len) })
+    }
+
+    // 要素を追加する。キャパシティが足りなければファイルを2倍に伸ばす
+    pub fn push(&mut self, element: T) -> Result<(), MmapError> {
+        if self.is_read_only() {
+            return Err(MmapError::ReadOnly);
+        }
+        if self.len == self.capacity() {
+            self.grow()?;
+        }
+        self.len += 1;
+        let last = self.len - 1;
+        self.as_mut_slice()?[last] = element;
+        self.write_header()
+    }
+
+    pub fn pop(&mut self) -> Result<Option<T>, MmapError> {
+        if self.is_read_only() {
+            return Err(MmapError::ReadOnly);
+        }
+        let last = match self.len.checked_sub(1) {
+            Some(last) => last,
+            None => return Ok(None),
+        };
+        let elem = self.as_slice()[last];
+        self.len = last;
+        self.write_header()?;
+        Ok(Some(elem))
+    }
+
+    // 変更をファイルへ書き出すよう要求する。書き出しの完了は待たない
+    pub fn flush(&self) -> Result<(), MmapError> {
+        if let Map::ReadWrite(map) = &self.map {
+            map.flush_async()?;
+        }
+        Ok(())
+    }
+
+    // 変更をファイルへ書き出し、ディスクに届くまで待つ
+    pub fn sync(&self) -> Result<(), MmapError> {
+        if let Map::ReadWrite(map) = &self.map {
+            map.flush()?;
+            self.file.sync_all()?;
+        }
+        Ok(())
+    }
+
+    // マップしたファイルのヘッダを検証する
+    fn from_map(file: File, map: Map) -> Result<Self, MmapError> {
+        Self::check_layout();
+        let bytes = map.bytes();
+        if bytes.len() < HEADER_LEN {
+            return Err(MmapError::TooShort {
+                file_len: bytes.len() as u64,
+            });
+        }
+        if bytes[0..8] != MAGIC {
+            return Err(MmapError::BadMagic);
+        }
+        let version = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+        if version != VERSION {
+            return Err(MmapError::UnsupportedVersion(version));
+        }
+        let elem_size = u32::from_le_bytes(bytes[12..16].try_into().unwrap()) as usize;
+        if elem_size != mem::size_of::<T>() {
+            return Err(MmapError::ElementSizeMismatch {
+                expected: mem::size_of::<T>(),
+                found: elem_size,
+            });
+        }
+        let len = u64::from_le_bytes(bytes[16..24].try_into().unwrap());
+        let capacity = (bytes.len() - HEADER_LEN) / elem_size;
+        if len > capacity as u64 {
+            return Err(MmapError::LengthOutOfRange { len, capacity });
+        }
+        Ok(Self {
+            file,
+            map,
+            len: len as usize,
+            _marker: PhantomData,
+        })
+    }
+
+    // ヘッダの直後に`T`を並べられることを確かめる
+    fn check_layout() {
+        assert!(
+            mem::size_of::<T>() > 0,
+            "zero-sized types are not supported"
+        );
+        assert!(
+            HEADER_LEN.is_multiple_of(mem::align_of::<T>()),
+            "element alignment must divide the header size"
+        );
+    }
+
+    fn map_mut(&mut self) -> Result<&mut MmapMut, MmapError> {
+        match &mut self.map {
+            Map::ReadWrite(map) => Ok(map),
+            Map::ReadOnly(_) => Err(MmapError::ReadOnly),
+        }
+    }
+
+    fn write_header(&mut self) -> Result<(), MmapError> {
+        let len = self.len as u64;
+        let map = self.map_mut()?;
+        map[0..8].copy_from_slice(&MAGIC);
+        map[8..12].copy_from_slice(&VERSION.to_le_bytes());
+        map[12..16].copy_from_slice(&(mem::size_of::<T>() as u32).to_le_bytes());
+        map[16..24].copy_from_slice(&len.to_le_bytes());
+        map[24..32].copy_from_slice(&[0; 8]);
+        Ok(())
+    }
+
+    // ファイルを伸ばしてマップし直す
+    fn grow(&mut self) -> Result<(), MmapError> {
+        let new_capacity = (self.capacity() * 2).max(INITIAL_CAPACITY);
+        if let Map::ReadWrite(map) = &self.map {
+            map.flush()?;
+        }
+        self.file.set_len(file_len::<T>(new_capacity))?;
+        self.map = Map::ReadWrite(unsafe { MmapMut::map_mut(&self.file)? });
+        Ok(())
+    }
+}
+
+fn file_len<T>(capacity: usize) -> u64 {
+    (HEADER_LEN + capacity * mem::size_of::<T>()) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MmapError, MmapToyVec};
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // テストごとに別のファイルを使い、テストが終わったら削除する
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new() -> Self {
+            static COUNTER: AtomicUsize = AtomicUsize::new(0);
+            let name = format!(
+                "toy_vec_mmap_{}_{}.bin",
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            );
+            TempFile(std::env::temp_dir().join(name))
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_push_and_reopen() {
+        let tmp = TempFile::new();
+        {
+            let mut v = MmapToyVec::<u32>::create(&tmp.0).unwrap();
+            for i in 0..1000 {
+                v.push(i).unwrap();
+            }
+            assert!(v.capacity() >= 1000);
+            assert_eq!(v.pop().unwrap(), Some(999));
+            v.as_mut_slice().unwrap()[0] = 42;
+            v.sync().unwrap();
+        }
+
+        let v = MmapToyVec::<u32>::open(&tmp.0).unwrap();
+        assert_eq!(v.len(), 999);
+        assert_eq!(v.get(0), Some(42));
+        assert_eq!(v.get(998), Some(998));
+        assert_eq!(v.get(999), None);
+    }
+
+    #[test]
+    fn test_read_only() {
+        let tmp = TempFile::new();
+        {
+            let mut v = MmapToyVec::<f64>::create(&tmp.0).unwrap();
+            v.push(1.5).unwrap();
+            v.flush().unwrap();
+        }
+        let mut v = MmapToyVec::<f64>::open_read_only(&tmp.0).unwrap();
+        assert_eq!(v.as_slice(), &[1.5]);
+        assert!(matches!(v.push(2.5), Err(MmapError::ReadOnly)));
+        assert!(matches!(v.as_mut_slice(), Err(MmapError::ReadOnly)));
+        assert_eq!(v.len(), 1);
+    }
+
+    #[test]
+    fn test_reject_mismatched_files() {
+        let tmp = TempFile::new();
+        {
+            let mut v = MmapToyVec::<u32>::create(&tmp.0).unwrap();
+            v.push(7).unwrap();
+        }
+        match MmapToyVec::<u64>::open(&tmp.0) {
+            Err(MmapError::ElementSizeMismatch { expected, found }) => {
+                assert_eq!((expected, found), (8, 4))
+            }
+            other => panic!("unexpected result: {:?}", other.err()),
+        }
+
+        // ヘッダを書き換えて壊す
+        let original = std::fs::read(&tmp.0).unwrap();
+        let corrupt = |offset: usize, bytes: &[u8]| {
+            let mut data = original.clone();
+            data[offset..offset + bytes.len()].copy_from_slice(bytes);
+            std::fs::write(&tmp.0, data).unwrap();
+            MmapToyVec::<u32>::open(&tmp.0).err()
+        };
+        assert!(matches!(corrupt(0, b"NOTAVEC!"), Some(MmapError::BadMagic)));
+        assert!(matches!(
+            corrupt(8, &2u32.to_le_bytes()),
+            Some(MmapError::UnsupportedVersion(2))
+        ));
+        assert!(matches!(
+            corrupt(16, &1_000_000u64.to_le_bytes()),
+            Some(MmapError::LengthOutOfRange { .. })
+        ));
+
+        std::fs::write(&tmp.0, b"TOYVEC").unwrap();
+        let err = MmapToyVec::<u32>::open(&tmp.0).err().unwrap();
+        assert!(matches!(err, MmapError::TooShort { file_len: 6 }));
+        assert_eq!(
+            err.to_string(),
+            "file is 6 bytes long, shorter than the 32-byte header"
+        );
+    }
+}
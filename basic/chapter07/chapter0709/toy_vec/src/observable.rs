@@ -0,0 +1,307 @@
+// 変更を購読者（リスナー）に通知する`ToyVec`
+// リスナーは`Weak`で保持するので、呼び出し側が`Rc`を手放せば自動的に購読が解除される
+use crate::ToyVec;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::{Rc, Weak};
+
+// ベクタに加えられた変更
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Pushed,
+    Popped,
+    Inserted { index: usize },
+    Removed { index: usize },
+    Updated { index: usize },
+    Cleared,
+    // トランザクションの中で起きた変更をまとめたもの（起きた順に並ぶ）
+    Batch(Vec<Change>),
+}
+
+// 変更を受け取るリスナー
+pub type Listener = dyn Fn(&Change);
+
+pub struct ObservableToyVec<T> {
+    elements: ToyVec<T>,
+    listeners: Vec<Weak<Listener>>,
+    // トランザクション中なら、まだ通知していない変更がここにたまる
+    pending: Option<Vec<Change>>,
+}
+
+impl<T: Default> ObservableToyVec<T> {
+    pub fn new() -> Self {
+        Self::from(ToyVec::new())
+    }
+
+    // リスナーを登録する。`listener`の`Rc`がすべてドロップされると、以降は呼ばれない
+    pub fn subscribe(&mut self, listener: &Rc<Listener>) {
+        self.listeners.push(Rc::downgrade(listener));
+    }
+
+    // まだ生きているリスナーの数を返す
+    pub fn subscriber_count(&mut self) -> usize {
+        self.prune();
+        self.listeners.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.elements.get(index)
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        self.elements.as_slice()
+    }
+
+    pub fn iter(&self) -> crate::Iter<'_, T> {
+        self.elements.iter()
+    }
+
+    pub fn push(&mut self, element: T) {
+        self.elements.push(element);
+        self.notify(Change::Pushed);
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let element = self.elements.pop()?;
+        self.notify(Change::Popped);
+        Some(element)
+    }
+
+    pub fn insert(&mut self, index: usize, element: T) {
+        self.elements.insert(index, element);
+        self.notify(Change::Inserted { index });
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        let element = self.elements.remove(index)?;
+        self.notify(Change::Removed { index });
+        Some(element)
+    }
+
+    // `index`の要素を置き換え、元の要素を返す。範囲外なら`None`を返し、何も通知しない
+    pub fn set(&mut self, index: usize, element: T) -> Option<T> {
+        let old = std::mem::replace(self.elements.get_mut(index)?, element);
+        self.notify(Change::Updated { index });
+        Some(old)
+    }
+
+    // `index`の要素を`f`で書き換える。範囲外なら`false`を返す
+    pub fn update<F: FnOnce(&mut T)>(&mut self, index: usize, f: F) -> bool {
+        match self.elements.get_mut(index) {
+            Some(element) => {
+                f(element);
+                self.notify(Change::Updated { index });
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        while self.elements.pop().is_some() {}
+        self.notify(Change::Cleared);
+    }
+
+    // `f`の中で加えた変更を、最後に1つの`Change::Batch`としてまとめて通知する
+    // 変更が1つだけならそのまま、1つもなければ何も通知しない
+    // トランザクションの中でさらに`transaction`を呼んだ場合は、外側のトランザクションにまとめる
+    // `f`がパニックした場合、それまでの変更は通知せずに捨て、パニックを呼び出し元で再開する
+    // （途中までの変更をパニックの最中にリスナーへ渡さないため）。以降の変更はまた通知される
+    pub fn transaction<R, F: FnOnce(&mut Self) -> R>(&mut self, f: F) -> R {
+        if self.pending.is_some() {
+            return f(self);
+        }
+        self.pending = Some(Vec::new());
+        let result = panic::catch_unwind(AssertUnwindSafe(|| f(self)));
+        let mut changes = self.pending.take().unwrap_or_default();
+        let result = result.unwrap_or_else(|e| panic::resume_unwind(e));
+        match changes.len() {
+            0 => {}
+            1 => self.emit(&changes.pop().unwrap()),
+            _ => self.emit(&Change::Batch(changes)),
+        }
+        result
+    }
+
+    // 中身の`ToyVec`を取り出す。以降の変更は通知されない
+    pub fn into_inner(self) -> ToyVec<T> {
+        self.elements
+    }
+
+    fn notify(&mut self, change: Change) {
+        match &mut self.pending {
+            Some(changes) => changes.push(change),
+            None => self.emit(&change),
+        }
+    }
+
+    fn emit(&mut self, change: &Change) {
+        self.listeners.retain(|listener| match listener.upgrade() {
+            Some(listener) => {
+                listener(change);
+                true
+            }
+            // 購読者がいなくなったリスナーは取り除く
+            None => false,
+        });
+    }
+
+    fn prune(&mut self) {
+        self.listeners
+            .retain(|listener| listener.strong_count() > 0);
+    }
+}
+
+impl<T: Default> From<ToyVec<T>> for ObservableToyVec<T> {
+    fn from(elements: ToyVec<T>) -> Self {
+        Self {
+            elements,
+            listeners: Vec::new(),
+            pending: None,
+        }
+    }
+}
+
+impl<T: Default> Default for ObservableToyVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ObservableToyVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.elements.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Change, Listener, ObservableToyVec};
+    use std::cell::RefCell;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::rc::Rc;
+
+    // 受け取った変更を記録するリスナーを作る
+    fn recorder() -> (Rc<Listener>, Rc<RefCell<Vec<Change>>>) {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let cloned = Rc::clone(&log);
+        let listener: Rc<Listener> = Rc::new(move |change: &Change| {
+            cloned.borrow_mut().push(change.clone());
+        });
+        (listener, log)
+    }
+
+    #[test]
+    fn test_changes_are_notified() {
+        let mut v = ObservableToyVec::new();
+        let (listener, log) = recorder();
+        v.subscribe(&listener);
+
+        v.push(1);
+        v.push(2);
+        v.insert(0, 0);
+        assert_eq!(v.set(1, 10), Some(1));
+        assert!(v.update(2, |x| *x += 1));
+        assert!(!v.update(3, |x| *x += 1));
+        assert_eq!(v.remove(0), Some(0));
+        assert_eq!(v.pop(), Some(3));
+        // 何も起きなかった操作は通知しない
+        assert_eq!(v.remove(5), None);
+        v.clear();
+        assert!(v.is_empty());
+        assert_eq!(v.pop(), None);
+
+        assert_eq!(
+            *log.borrow(),
+            vec![
+                Change::Pushed,
+                Change::Pushed,
+                Change::Inserted { index: 0 },
+                Change::Updated { index: 1 },
+                Change::Updated { index: 2 },
+                Change::Removed { index: 0 },
+                Change::Popped,
+                Change::Cleared,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dropped_listeners_are_pruned() {
+        let mut v = ObservableToyVec::new();
+        let (first, first_log) = recorder();
+        let (second, second_log) = recorder();
+        v.subscribe(&first);
+        v.subscribe(&second);
+        v.push("a");
+        assert_eq!(v.subscriber_count(), 2);
+
+        drop(second);
+        v.push("b");
+        assert_eq!(v.subscriber_count(), 1);
+        assert_eq!(first_log.borrow().len(), 2);
+        assert_eq!(second_log.borrow().len(), 1);
+    }
+
+    #[test]
+    fn test_transaction_emits_one_event() {
+        let mut v = ObservableToyVec::new();
+        let (listener, log) = recorder();
+        v.subscribe(&listener);
+
+        let len = v.transaction(|v| {
+            v.push(1);
+            v.push(2);
+            // 入れ子のトランザクションも外側にまとめる
+            v.transaction(|v| v.set(0, 5));
+            v.len()
+        });
+        assert_eq!(len, 2);
+        v.transaction(|v| v.push(3));
+        // 何も変更しなければ通知しない
+        v.transaction(|v| v.get(0).copied());
+
+        assert_eq!(
+            *log.borrow(),
+            vec![
+                Change::Batch(vec![
+                    Change::Pushed,
+                    Change::Pushed,
+                    Change::Updated { index: 0 },
+                ]),
+                Change::Pushed,
+            ]
+        );
+        assert_eq!(v.as_slice(), &[5, 2, 3]);
+    }
+
+    #[test]
+    fn test_panic_in_transaction() {
+        let mut v = ObservableToyVec::new();
+        let (listener, log) = recorder();
+        v.subscribe(&listener);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            v.transaction(|v| {
+                v.push(1);
+                panic!("boom");
+            })
+        }));
+        assert!(result.is_err());
+        // パニックしたトランザクションの変更は通知されないが、要素には残る
+        assert!(log.borrow().is_empty());
+        assert_eq!(v.as_slice(), &[1]);
+
+        // トランザクションの外の変更は、またすぐに通知される
+        v.push(2);
+        assert_eq!(*log.borrow(), vec![Change::Pushed]);
+    }
+}
@@ -0,0 +1,255 @@
+use crate::ToyVec;
+use std::mem;
+
+// 1ブロックに入れる値の数
+const BLOCK_LEN: usize = 128;
+
+// 圧縮済みのブロック1つ分の情報
+#[derive(Debug, Clone, Copy, Default)]
+struct Block {
+    // ブロックの先頭の値。ブロック内の値はこの値との差分で表す
+    base: u64,
+    // 差分1つあたりのビット数
+    width: u32,
+    // ブロックの差分が始まる`words`内の位置
+    offset: usize,
+}
+
+// 昇順に並んだ`u64`を詰めて格納するベクタ
+// 値を`BLOCK_LEN`個ずつのブロックに分け、ブロックの先頭の値（基準値）からの差分を、
+// ブロック内の最大の差分を表せるだけのビット数で詰めて並べる（frame of reference）
+// 隣り合う値の差が小さければ、1つの値に使うのは8バイトよりずっと少なくなる
+pub struct PackedToyVec {
+    blocks: ToyVec<Block>,
+    // すべてのブロックの差分を詰めたビット列
+    words: ToyVec<u64>,
+    // まだブロックになっていない末尾の値。`BLOCK_LEN`個たまったら圧縮する
+    tail: ToyVec<u64>,
+    len: usize,
+}
+
+impl PackedToyVec {
+    pub fn new() -> Self {
+        Self {
+            blocks: ToyVec::new(),
+            words: ToyVec::new(),
+            tail: ToyVec::with_capacity(BLOCK_LEN),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn last(&self) -> Option<u64> {
+        self.len.checked_sub(1).and_then(|i| self.get(i))
+    }
+
+    // 値を追加する。値は直前に追加した値以上でなければならない
+    // ブロックの圧縮は`BLOCK_LEN`回に1回なので、償却O(1)
+    pub fn push(&mut self, value: u64) {
+        if let Some(last) = self.last() {
+            assert!(
+                last <= value,
+                "values must be pushed in non-decreasing order (last is {}, pushed {})",
+                last,
+                value
+            );
+        }
+        self.tail.push(value);
+        self.len += 1;
+        if self.tail.len() == BLOCK_LEN {
+            self.pack_tail();
+        }
+    }
+
+    // `index`番目の値を返す。ブロックの位置と差分のビット位置は計算で求まるので、O(1)
+    pub fn get(&self, index: usize) -> Option<u64> {
+        if index >= self.len {
+            return None;
+        }
+        let (block, pos) = (index / BLOCK_LEN, index % BLOCK_LEN);
+        match self.blocks.get(block) {
+            Some(b) => {
+                let bit = b.offset * 64 + pos * b.width as usize;
+                Some(b.base + read_bits(self.words.as_slice(), bit, b.width))
+            }
+            None => self.tail.get(pos).copied(),
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { vec: self, pos: 0 }
+    }
+
+    // 確保している領域のバイト数を返す
+    pub fn memory_usage(&self) -> usize {
+        mem::size_of::<Self>()
+            + self.blocks.capacity() * mem::size_of::<Block>()
+            + self.words.capacity() * mem::size_of::<u64>()
+            + self.tail.capacity() * mem::size_of::<u64>()
+    }
+
+    // 末尾にたまった値を1つのブロックに圧縮する
+    fn pack_tail(&mut self) {
+        let values = self.tail.as_slice();
+        let base = values[0];
+        // 昇順なので、最大の差分は末尾の値との差
+        let max_delta = values[values.len() - 1] - base;
+        let width = u64::BITS - max_delta.leading_zeros();
+
+        let offset = self.words.len();
+        let words = (values.len() * width as usize).div_ceil(64);
+        for _ in 0..words {
+            self.words.push(0);
+        }
+        let packed = &mut self.words.as_mut_slice()[offset..];
+        for (i, &value) in values.iter().enumerate() {
+            write_bits(packed, i * width as usize, width, value - base);
+        }
+
+        self.blocks.push(Block {
+            base,
+            width,
+            offset,
+        });
+        while self.tail.pop().is_some() {}
+    }
+}
+
+impl Default for PackedToyVec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::iter::FromIterator<u64> for PackedToyVec {
+    fn from_iter<I: IntoIterator<Item = u64>>(iter: I) -> Self {
+        let mut v = Self::new();
+        for value in iter {
+            v.push(value);
+        }
+        v
+    }
+}
+
+fn mask(width: u32) -> u64 {
+    if width == 64 {
+        u64::MAX
+    } else {
+        (1 << width) - 1
+    }
+}
+
+// `bit`ビット目から`width`ビットを読む。値は2つのワードにまたがることがある
+fn read_bits(words: &[u64], bit: usize, width: u32) -> u64 {
+    if width == 0 {
+        return 0;
+    }
+    let (word, shift) = (bit / 64, (bit % 64) as u32);
+    let mut value = words[word] >> shift;
+    if shift + width > 64 {
+        value |= words[word + 1] << (64 - shift);
+    }
+    value & mask(width)
+}
+
+// `bit`ビット目から`width`ビットに`value`を書く。書く先は0で初期化されていなければならない
+fn write_bits(words: &mut [u64], bit: usize, width: u32, value: u64) {
+    if width == 0 {
+        return;
+    }
+    let (word, shift) = (bit / 64, (bit % 64) as u32);
+    words[word] |= value << shift;
+    if shift + width > 64 {
+        words[word + 1] |= value >> (64 - shift);
+    }
+}
+
+pub struct Iter<'vec> {
+    vec: &'vec PackedToyVec,
+    pos: usize,
+}
+
+impl<'vec> Iterator for Iter<'vec> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.vec.get(self.pos)?;
+        self.pos += 1;
+        Some(value)
+    }
+}
+
+impl<'vec> IntoIterator for &'vec PackedToyVec {
+    type Item = u64;
+    type IntoIter = Iter<'vec>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PackedToyVec, BLOCK_LEN};
+
+    #[test]
+    fn test_push_and_get() {
+        // 差が0から大きな値まで混じるID
+        let mut ids = Vec::new();
+        let mut id = 1_000_000_000u64;
+        for i in 0..1000u64 {
+            id += i % 7;
+            if i % 300 == 299 {
+                id += 1 << 40;
+            }
+            ids.push(id);
+        }
+        let v: PackedToyVec = ids.iter().copied().collect();
+        assert_eq!(v.len(), ids.len());
+        for (i, &id) in ids.iter().enumerate() {
+            assert_eq!(v.get(i), Some(id));
+        }
+        assert_eq!(v.get(ids.len()), None);
+        assert!(v.iter().eq(ids.iter().copied()));
+    }
+
+    #[test]
+    fn test_extreme_values() {
+        // 差分に64ビットすべてが必要なブロックと、差分が0のブロック
+        let mut v = PackedToyVec::new();
+        v.push(0);
+        for _ in 1..BLOCK_LEN {
+            v.push(u64::MAX);
+        }
+        for _ in 0..BLOCK_LEN + 1 {
+            v.push(u64::MAX);
+        }
+        assert_eq!(v.get(0), Some(0));
+        assert!(v.iter().skip(1).all(|x| x == u64::MAX));
+        assert_eq!(v.last(), Some(u64::MAX));
+    }
+
+    #[test]
+    fn test_memory_usage() {
+        // 差が小さいIDなら、`ToyVec<u64>`の8バイト/要素よりずっと小さくなる
+        let n = 100_000;
+        let v: PackedToyVec = (0..n as u64).map(|i| i * 3 + i % 2).collect();
+        assert!(v.memory_usage() < n * 8 / 4);
+        assert_eq!(v.get(n - 1), Some((n as u64 - 1) * 3 + 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "non-decreasing")]
+    fn test_decreasing_push_panics() {
+        let mut v = PackedToyVec::new();
+        v.push(10);
+        v.push(9);
+    }
+}
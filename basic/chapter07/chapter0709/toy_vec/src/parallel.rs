@@ -0,0 +1,340 @@
+// `parallel`フィーチャーを有効にすると使える、`ToyVec`の並列処理
+// 要素を重ならないチャンクに分け、`std::thread::scope`で起動したスレッドで処理する
+// スコープ付きスレッドはスコープを抜ける前に必ず終了するので、`ToyVec`の要素を借用したまま渡せる
+use crate::ToyVec;
+use std::thread;
+
+// これより短いチャンクにはしない。スレッドを起動するコストの方が大きくなるため
+const MIN_CHUNK_LEN: usize = 1024;
+
+// 使えるCPUの数だけスレッドを使う
+fn threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+// `len`個の要素を各スレッドに割り振るときの、1スレッドあたりの要素数
+fn chunk_len(len: usize) -> usize {
+    let threads = threads();
+    len.div_ceil(threads).max(MIN_CHUNK_LEN)
+}
+
+impl<T: Default> ToyVec<T> {
+    // 要素への参照を並列に処理するイテレータを作る
+    pub fn par_iter(&self) -> ParIter<'_, T>
+    where
+        T: Sync,
+    {
+        ParIter {
+            slice: self.as_slice(),
+        }
+    }
+
+    // 要素への可変の参照を並列に処理するイテレータを作る
+    pub fn par_iter_mut(&mut self) -> ParIterMut<'_, T>
+    where
+        T: Send,
+    {
+        ParIterMut {
+            slice: self.as_mut_slice(),
+        }
+    }
+
+    // 要素を`chunk_size`個ずつのスライスに分け、並列に処理するイテレータを作る
+    pub fn par_chunks_mut(&mut self, chunk_size: usize) -> ParChunksMut<'_, T>
+    where
+        T: Send,
+    {
+        assert!(chunk_size != 0, "chunk size must be non-zero");
+        ParChunksMut {
+            slice: self.as_mut_slice(),
+            chunk_size,
+        }
+    }
+
+    // 並列に（安定）ソートする
+    // 前半と後半を別のスレッドでソートしてからマージすることを、スレッドを使い切るまで繰り返す
+    pub fn par_sort(&mut self)
+    where
+        T: Ord + Send,
+    {
+        let len = self.len();
+        // マージに使う作業領域
+        let mut buf = Self::allocate_in_heap(len);
+        merge_sort(self.as_mut_slice(), &mut buf, threads());
+    }
+}
+
+pub struct ParIter<'vec, T> {
+    slice: &'vec [T],
+}
+
+impl<'vec, T: Sync> ParIter<'vec, T> {
+    pub fn for_each<F>(self, f: F)
+    where
+        F: Fn(&T) + Sync,
+    {
+        let f = &f;
+        thread::scope(|s| {
+            for chunk in self.slice.chunks(chunk_len(self.slice.len())) {
+                s.spawn(move || chunk.iter().for_each(f));
+            }
+        });
+    }
+
+    // 各要素に`f`を適用した結果を、元の順序のまま`ToyVec`に集める
+    pub fn map<U, F>(self, f: F) -> ToyVec<U>
+    where
+        U: Default + Send,
+        F: Fn(&T) -> U + Sync,
+    {
+        let f = &f;
+        let parts: Vec<ToyVec<U>> = thread::scope(|s| {
+            let handles: Vec<_> = self
+                .slice
+                .chunks(chunk_len(self.slice.len()))
+                .map(|chunk| {
+                    s.spawn(move || {
+                        let mut part = ToyVec::with_capacity(chunk.len());
+                        for elem in chunk {
+                            part.push(f(elem));
+                        }
+                        part
+                    })
+                })
+                .collect();
+            handles.into_iter().map(join).collect()
+        });
+
+        let mut mapped = ToyVec::with_capacity(self.slice.len());
+        for part in parts {
+            for elem in part {
+                mapped.push(elem);
+            }
+        }
+        mapped
+    }
+
+    // 各要素を`map`で変換し、`reduce`で1つにまとめる
+    // 各スレッドがチャンク内をまとめた後、その結果をさらにまとめる。`reduce`は結合的でなければならない
+    pub fn map_reduce<U, M, R>(self, identity: U, map: M, reduce: R) -> U
+    where
+        U: Clone + Send + Sync,
+        M: Fn(&T) -> U + Sync,
+        R: Fn(U, U) -> U + Sync,
+    {
+        let (map, reduce, init) = (&map, &reduce, &identity);
+        let partials: Vec<U> = thread::scope(|s| {
+            let handles: Vec<_> = self
+                .slice
+                .chunks(chunk_len(self.slice.len()))
+                .map(|chunk| {
+                    s.spawn(move || {
+                        chunk
+                            .iter()
+                            .fold(init.clone(), |acc, elem| reduce(acc, map(elem)))
+                    })
+                })
+                .collect();
+            handles.into_iter().map(join).collect()
+        });
+        partials.into_iter().fold(identity.clone(), reduce)
+    }
+}
+
+pub struct ParIterMut<'vec, T> {
+    slice: &'vec mut [T],
+}
+
+impl<'vec, T: Send> ParIterMut<'vec, T> {
+    pub fn for_each<F>(self, f: F)
+    where
+        F: Fn(&mut T) + Sync,
+    {
+        let f = &f;
+        let len = chunk_len(self.slice.len());
+        thread::scope(|s| {
+            for chunk in self.slice.chunks_mut(len) {
+                s.spawn(move || chunk.iter_mut().for_each(f));
+            }
+        });
+    }
+}
+
+pub struct ParChunksMut<'vec, T> {
+    slice: &'vec mut [T],
+    chunk_size: usize,
+}
+
+impl<'vec, T: Send> ParChunksMut<'vec, T> {
+    pub fn for_each<F>(self, f: F)
+    where
+        F: Fn(&mut [T]) + Sync,
+    {
+        let f = &f;
+        let chunk_size = self.chunk_size;
+        // 各スレッドには`chunk_size`の倍数の要素を割り振り、チャンクがスレッドをまたがないようにする
+        let per_thread = chunk_len(self.slice.len()).div_ceil(chunk_size) * chunk_size;
+        thread::scope(|s| {
+            for batch in self.slice.chunks_mut(per_thread) {
+                s.spawn(move || batch.chunks_mut(chunk_size).for_each(f));
+            }
+        });
+    }
+}
+
+// スレッドがパニックしていたら、そのパニックを呼び出し元で再開する
+fn join<R>(handle: thread::ScopedJoinHandle<'_, R>) -> R {
+    handle
+        .join()
+        .unwrap_or_else(|e| std::panic::resume_unwind(e))
+}
+
+// `v`をソートする。`buf`は`v`と同じ長さの作業領域
+fn merge_sort<T>(v: &mut [T], buf: &mut [T], threads: usize)
+where
+    T: Ord + Send + Default,
+{
+    if threads <= 1 || v.len() <= MIN_CHUNK_LEN {
+        v.sort();
+        return;
+    }
+
+    let mid = v.len() / 2;
+    {
+        let (left, right) = v.split_at_mut(mid);
+        let (left_buf, right_buf) = buf.split_at_mut(mid);
+        thread::scope(|s| {
+            s.spawn(|| merge_sort(left, left_buf, threads / 2));
+            merge_sort(right, right_buf, threads - threads / 2);
+        });
+    }
+    merge(v, mid, buf);
+}
+
+// ソート済みの`v[..mid]`と`v[mid..]`をマージする
+// 要素は`std::mem::take`で`buf`へムーブし、最後に`v`と入れ替える
+fn merge<T: Ord + Default>(v: &mut [T], mid: usize, buf: &mut [T]) {
+    let (mut i, mut j) = (0, mid);
+    for slot in buf.iter_mut() {
+        // 同じ値なら前半を先にすることで、安定ソートになる
+        let take_left = j >= v.len() || (i < mid && v[i] <= v[j]);
+        if take_left {
+            *slot = std::mem::take(&mut v[i]);
+            i += 1;
+        } else {
+            *slot = std::mem::take(&mut v[j]);
+            j += 1;
+        }
+    }
+    v.swap_with_slice(buf);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::merge_sort;
+    use crate::ToyVec;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn sample(n: usize) -> ToyVec<u64> {
+        let mut v = ToyVec::with_capacity(n);
+        // 線形合同法で作った擬似乱数を並べる
+        let mut x: u64 = 12345;
+        for _ in 0..n {
+            x = x
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            v.push(x >> 40);
+        }
+        v
+    }
+
+    #[test]
+    fn test_par_iter() {
+        let v = sample(100_000);
+        let count = AtomicUsize::new(0);
+        v.par_iter().for_each(|_| {
+            count.fetch_add(1, Ordering::Relaxed);
+        });
+        assert_eq!(count.load(Ordering::Relaxed), v.len());
+
+        let doubled = v.par_iter().map(|x| x * 2);
+        assert!(doubled.iter().zip(v.iter()).all(|(d, x)| *d == x * 2));
+
+        let sum = v.par_iter().map_reduce(0, |x| *x, |a, b| a + b);
+        assert_eq!(sum, v.iter().sum());
+        let max = v.par_iter().map_reduce(0, |x| *x, |a, b| a.max(b));
+        assert_eq!(Some(&max), v.iter().max());
+    }
+
+    #[test]
+    fn test_par_iter_mut_and_chunks() {
+        let mut v = sample(50_000);
+        let expected: Vec<_> = v.iter().map(|x| x + 1).collect();
+        v.par_iter_mut().for_each(|x| *x += 1);
+        assert_eq!(v.as_slice(), &expected[..]);
+
+        // 各チャンクの先頭にチャンクの長さを書き込む
+        v.par_chunks_mut(7)
+            .for_each(|chunk| chunk[0] = chunk.len() as u64);
+        let heads: Vec<_> = v.as_slice().chunks(7).map(|c| c[0]).collect();
+        assert_eq!(heads.len(), 50_000usize.div_ceil(7));
+        assert!(heads[..heads.len() - 1].iter().all(|&h| h == 7));
+        assert_eq!(heads[heads.len() - 1], (50_000 % 7) as u64);
+    }
+
+    // `key`だけで比較する要素
+    #[derive(Debug, Default)]
+    struct Item {
+        key: u32,
+        seq: u32,
+    }
+
+    impl PartialEq for Item {
+        fn eq(&self, other: &Self) -> bool {
+            self.key == other.key
+        }
+    }
+
+    impl Eq for Item {}
+
+    impl PartialOrd for Item {
+        fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Item {
+        fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+            self.key.cmp(&other.key)
+        }
+    }
+
+    #[test]
+    fn test_par_sort() {
+        for &n in &[0, 1, 1000, 100_003] {
+            let mut v = sample(n);
+            let mut expected: Vec<_> = v.iter().copied().collect();
+            expected.sort();
+            v.par_sort();
+            assert_eq!(v.as_slice(), &expected[..]);
+
+            // CPUが1つしかない環境でもマージを確かめられるよう、スレッド数を指定して呼ぶ
+            let mut v = sample(n);
+            let mut buf = ToyVec::allocate_in_heap(n);
+            merge_sort(v.as_mut_slice(), &mut buf, 4);
+            assert_eq!(v.as_slice(), &expected[..]);
+        }
+
+        // 安定ソートなので、同じキーの要素は元の順序を保つ
+        let mut items = ToyVec::new();
+        for seq in 0..20_000 {
+            items.push(Item { key: seq % 10, seq });
+        }
+        let mut buf = ToyVec::allocate_in_heap(items.len());
+        merge_sort(items.as_mut_slice(), &mut buf, 8);
+        assert!(items
+            .as_slice()
+            .windows(2)
+            .all(|w| w[0].key < w[1].key || (w[0].key == w[1].key && w[0].seq < w[1].seq)));
+    }
+}
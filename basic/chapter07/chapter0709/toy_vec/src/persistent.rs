@@ -0,0 +1,412 @@
+use crate::ToyVec;
+use std::fmt;
+use std::rc::Rc;
+
+// 1つのノードが持つ子の数は2^5 = 32
+const BITS: usize = 5;
+const WIDTH: usize = 1 << BITS;
+const MASK: usize = WIDTH - 1;
+
+// トライ木のノード。末端の`Leaf`が要素を32個ずつ持ち、`Branch`は子ノードを最大32個持つ
+enum Node<T> {
+    Branch(ToyVec<Option<Rc<Node<T>>>>),
+    Leaf(ToyVec<T>),
+}
+
+// `Rc::make_mut`で共有中のノードを複製するために使う
+// 子ノードは`Rc`を複製するだけなので、複製されるのはこのノード1つ分だけ
+impl<T: Clone + Default> Clone for Node<T> {
+    fn clone(&self) -> Self {
+        match self {
+            Node::Branch(children) => Node::Branch(children.clone()),
+            Node::Leaf(values) => Node::Leaf(values.clone()),
+        }
+    }
+}
+
+// 構造を共有する永続（イミュータブル）なベクタ
+// 要素は32分木のトライ木に格納し、末尾の最大32要素は`tail`に置く
+// 変更は新しいベクタを返し、変更前のベクタはそのまま残る。変更で複製されるのは
+// 根から変更箇所までの経路上のノードだけで、それ以外のノードは新旧のベクタで共有する
+pub struct PersistentToyVec<T> {
+    len: usize,
+    // 根ノードの階層。根から葉へ1段下るごとに`BITS`ずつ減る
+    shift: usize,
+    root: Rc<Node<T>>,
+    tail: Rc<ToyVec<T>>,
+}
+
+// `Clone`は参照カウントを増やすだけなのでO(1)
+impl<T> Clone for PersistentToyVec<T> {
+    fn clone(&self) -> Self {
+        Self {
+            len: self.len,
+            shift: self.shift,
+            root: Rc::clone(&self.root),
+            tail: Rc::clone(&self.tail),
+        }
+    }
+}
+
+impl<T: Clone + Default> PersistentToyVec<T> {
+    pub fn new() -> Self {
+        Self {
+            len: 0,
+            shift: BITS,
+            root: Rc::new(Node::Branch(ToyVec::new())),
+            tail: Rc::new(ToyVec::new()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // 要素を追加した新しいベクタを返す。O(log32 n)
+    pub fn push(&self, element: T) -> Self {
+        let mut pushed = self.clone();
+        pushed.push_mut(element);
+        pushed
+    }
+
+    // `index`の要素を置き換えた新しいベクタを返す。範囲外なら`None`を返す。O(log32 n)
+    pub fn set(&self, index: usize, element: T) -> Option<Self> {
+        if index >= self.len {
+            return None;
+        }
+        let mut updated = self.clone();
+        updated.set_mut(index, element);
+        Some(updated)
+    }
+
+    // O(log32 n)
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        self.leaf_for(index).get(index & MASK)
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            vec: self,
+            leaf: &[],
+            index: 0,
+        }
+    }
+
+    // 一時的に変更可能なビルダーに変換する
+    // ビルダーは自分だけが参照するノードをその場で書き換えるので、まとめて変更するときに速い
+    pub fn transient(&self) -> TransientToyVec<T> {
+        TransientToyVec { vec: self.clone() }
+    }
+
+    // 末尾の要素が入る位置（`tail`より前の要素数）
+    fn tail_offset(&self) -> usize {
+        if self.len < WIDTH {
+            0
+        } else {
+            ((self.len - 1) >> BITS) << BITS
+        }
+    }
+
+    // `index`の要素を含む32要素分の配列を返す
+    fn leaf_for(&self, index: usize) -> &[T] {
+        if index >= self.tail_offset() {
+            return self.tail.as_slice();
+        }
+        let mut node = &*self.root;
+        let mut level = self.shift;
+        loop {
+            match node {
+                Node::Branch(children) => {
+                    match children.get((index >> level) & MASK) {
+                        Some(Some(child)) => node = child,
+                        _ => return &[],
+                    }
+                    level -= BITS;
+                }
+                Node::Leaf(values) => return values.as_slice(),
+            }
+        }
+    }
+
+    // `Rc::make_mut`は参照カウントが1ならその場で書き換え、共有されていれば複製してから書き換える
+    fn push_mut(&mut self, element: T) {
+        if self.len - self.tail_offset() < WIDTH {
+            Rc::make_mut(&mut self.tail).push(element);
+            self.len += 1;
+            return;
+        }
+
+        // `tail`が一杯なので、葉としてトライ木に入れる
+        let tail = std::mem::replace(&mut self.tail, Rc::new(ToyVec::with_capacity(WIDTH)));
+        let leaf = Rc::new(Node::Leaf(
+            Rc::try_unwrap(tail).unwrap_or_else(|shared| (*shared).clone()),
+        ));
+        if (self.len >> BITS) > (1 << self.shift) {
+            // 根が一杯なので、1段高い根を作る
+            let old_root = std::mem::replace(&mut self.root, Rc::new(Node::Branch(ToyVec::new())));
+            let mut children = ToyVec::with_capacity(WIDTH);
+            children.push(Some(old_root));
+            children.push(Some(new_path(self.shift, leaf)));
+            self.root = Rc::new(Node::Branch(children));
+            self.shift += BITS;
+        } else {
+            push_tail(Rc::make_mut(&mut self.root), self.len - 1, self.shift, leaf);
+        }
+        Rc::make_mut(&mut self.tail).push(element);
+        self.len += 1;
+    }
+
+    fn set_mut(&mut self, index: usize, element: T) {
+        if index >= self.tail_offset() {
+            if let Some(slot) = Rc::make_mut(&mut self.tail).get_mut(index & MASK) {
+                *slot = element;
+            }
+        } else {
+            set_in(Rc::make_mut(&mut self.root), index, self.shift, element);
+        }
+    }
+}
+
+// `level`段分の枝を作り、その末端に`node`をぶら下げる
+fn new_path<T>(level: usize, node: Rc<Node<T>>) -> Rc<Node<T>>
+where
+    T: Clone + Default,
+{
+    if level == 0 {
+        node
+    } else {
+        let mut children = ToyVec::with_capacity(WIDTH);
+        children.push(Some(new_path(level - BITS, node)));
+        Rc::new(Node::Branch(children))
+    }
+}
+
+// 最後の要素の位置が`index`になるように、葉`leaf`をトライ木の右端へ追加する
+fn push_tail<T>(parent: &mut Node<T>, index: usize, level: usize, leaf: Rc<Node<T>>)
+where
+    T: Clone + Default,
+{
+    if let Node::Branch(children) = parent {
+        let subindex = (index >> level) & MASK;
+        let child = if level == BITS {
+            leaf
+        } else if let Some(Some(child)) = children.get_mut(subindex) {
+            push_tail(Rc::make_mut(child), index, level - BITS, leaf);
+            return;
+        } else {
+            new_path(level - BITS, leaf)
+        };
+        // トライ木は左から順に埋まるので、新しい子は常に末尾に入る
+        debug_assert_eq!(children.len(), subindex);
+        children.push(Some(child));
+    }
+}
+
+fn set_in<T>(node: &mut Node<T>, index: usize, level: usize, element: T)
+where
+    T: Clone + Default,
+{
+    match node {
+        Node::Branch(children) => {
+            if let Some(Some(child)) = children.get_mut((index >> level) & MASK) {
+                set_in(Rc::make_mut(child), index, level - BITS, element);
+            }
+        }
+        Node::Leaf(values) => {
+            if let Some(slot) = values.get_mut(index & MASK) {
+                *slot = element;
+            }
+        }
+    }
+}
+
+impl<T: Clone + Default> Default for PersistentToyVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone + Default + PartialEq> PartialEq for PersistentToyVec<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Clone + Default + fmt::Debug> fmt::Debug for PersistentToyVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Clone + Default> std::iter::FromIterator<T> for PersistentToyVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut transient = Self::new().transient();
+        for element in iter {
+            transient.push(element);
+        }
+        transient.persistent()
+    }
+}
+
+impl<'vec, T: Clone + Default> IntoIterator for &'vec PersistentToyVec<T> {
+    type Item = &'vec T;
+    type IntoIter = Iter<'vec, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// 要素へのイミュータブルな参照を返すイテレータ
+// 葉を1つずつたどるので、要素ごとにトライ木を根から下る必要はない
+pub struct Iter<'vec, T> {
+    vec: &'vec PersistentToyVec<T>,
+    // 現在たどっている葉（`index`を含む32要素分の配列）
+    leaf: &'vec [T],
+    index: usize,
+}
+
+impl<'vec, T: Clone + Default> Iterator for Iter<'vec, T> {
+    type Item = &'vec T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.vec.len {
+            return None;
+        }
+        if self.index & MASK == 0 {
+            self.leaf = self.vec.leaf_for(self.index);
+        }
+        let elem = self.leaf.get(self.index & MASK);
+        self.index += 1;
+        elem
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let rest = self.vec.len - self.index;
+        (rest, Some(rest))
+    }
+}
+
+//
+// 一時的に変更可能なビルダー
+//
+
+// `PersistentToyVec`を`&mut self`で変更するためのビルダー
+// 他のベクタと共有しているノードは最初の変更時に複製し、以降はその場で書き換える
+pub struct TransientToyVec<T> {
+    vec: PersistentToyVec<T>,
+}
+
+impl<T: Clone + Default> TransientToyVec<T> {
+    pub fn len(&self) -> usize {
+        self.vec.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vec.is_empty()
+    }
+
+    pub fn push(&mut self, element: T) {
+        self.vec.push_mut(element);
+    }
+
+    // `index`の要素を置き換える。範囲外なら何もせずに`false`を返す
+    pub fn set(&mut self, index: usize, element: T) -> bool {
+        if index >= self.vec.len {
+            return false;
+        }
+        self.vec.set_mut(index, element);
+        true
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.vec.get(index)
+    }
+
+    // 変更を終えて永続なベクタに戻す
+    pub fn persistent(self) -> PersistentToyVec<T> {
+        self.vec
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PersistentToyVec;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_push_and_get() {
+        // 根が3段以上になる（32 * 32 * 32を超える）まで追加する
+        let n = 40_000;
+        let v: PersistentToyVec<usize> = (0..n).collect();
+        assert_eq!(v.len(), n);
+        for i in (0..n).step_by(97) {
+            assert_eq!(v.get(i), Some(&i));
+        }
+        assert_eq!(v.get(n - 1), Some(&(n - 1)));
+        assert_eq!(v.get(n), None);
+        assert!(v.iter().copied().eq(0..n));
+    }
+
+    #[test]
+    fn test_persistence() {
+        let mut versions = vec![PersistentToyVec::new()];
+        for i in 0..100 {
+            let next = versions[i].push(i);
+            versions.push(next);
+        }
+        // 以前のバージョンは変更されない
+        for (len, version) in versions.iter().enumerate() {
+            assert_eq!(version.len(), len);
+            assert!(version.iter().copied().eq(0..len));
+        }
+
+        let v = &versions[100];
+        let w = v.set(3, 300).unwrap();
+        let x = w.set(99, 990).unwrap();
+        assert_eq!(v.get(3), Some(&3));
+        assert_eq!(w.get(3), Some(&300));
+        assert_eq!(w.get(99), Some(&99));
+        assert_eq!(x.get(3), Some(&300));
+        assert_eq!(x.get(99), Some(&990));
+        assert!(v.set(100, 0).is_none());
+    }
+
+    #[test]
+    fn test_clone_shares_structure() {
+        let v: PersistentToyVec<i32> = (0..1000).collect();
+        let c = v.clone();
+        assert!(Rc::ptr_eq(&v.root, &c.root));
+        assert!(Rc::ptr_eq(&v.tail, &c.tail));
+        assert_eq!(v, c);
+
+        // 末尾への追加では根は共有したまま
+        let d = v.push(1000);
+        assert!(Rc::ptr_eq(&v.root, &d.root));
+        assert_ne!(v, d);
+    }
+
+    #[test]
+    fn test_transient() {
+        let v: PersistentToyVec<String> = (0..100).map(|i| i.to_string()).collect();
+        let mut t = v.transient();
+        for i in 0..100 {
+            t.set(i, format!("#{}", i));
+        }
+        t.push("new".to_string());
+        assert!(!t.set(1000, String::new()));
+        let w = t.persistent();
+
+        assert_eq!(v.get(42).map(String::as_str), Some("42"));
+        assert_eq!(w.get(42).map(String::as_str), Some("#42"));
+        assert_eq!(w.get(100).map(String::as_str), Some("new"));
+        assert_eq!(w.len(), 101);
+    }
+}
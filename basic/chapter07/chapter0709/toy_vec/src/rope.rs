@@ -0,0 +1,584 @@
+use crate::ToyVec;
+use std::fmt;
+use std::ops::Range;
+
+// 1つの葉に入れる最大の文字数
+const MAX_LEAF: usize = 512;
+// 木の深さがこれを超えたら、葉を並べ直して平衡な木に作り直す
+const MAX_DEPTH: usize = 32;
+
+// ロープの節。各節は部分木の文字数と改行の数を覚えておき、位置や行から葉を探すときに使う
+enum Node {
+    Leaf {
+        text: String,
+        chars: usize,
+        newlines: usize,
+    },
+    Branch {
+        left: Box<Node>,
+        right: Box<Node>,
+        chars: usize,
+        newlines: usize,
+        depth: usize,
+    },
+}
+
+impl Node {
+    fn leaf(text: String) -> Node {
+        let chars = text.chars().count();
+        let newlines = text.matches('\n').count();
+        Node::Leaf {
+            text,
+            chars,
+            newlines,
+        }
+    }
+
+    fn empty() -> Node {
+        Node::leaf(String::new())
+    }
+
+    fn branch(left: Node, right: Node) -> Node {
+        Node::Branch {
+            chars: left.chars() + right.chars(),
+            newlines: left.newlines() + right.newlines(),
+            depth: left.depth().max(right.depth()) + 1,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    fn chars(&self) -> usize {
+        match self {
+            Node::Leaf { chars, .. } | Node::Branch { chars, .. } => *chars,
+        }
+    }
+
+    fn newlines(&self) -> usize {
+        match self {
+            Node::Leaf { newlines, .. } | Node::Branch { newlines, .. } => *newlines,
+        }
+    }
+
+    fn depth(&self) -> usize {
+        match self {
+            Node::Leaf { .. } => 0,
+            Node::Branch { depth, .. } => *depth,
+        }
+    }
+
+    // 2つの木をつなげる。どちらかが空ならもう一方をそのまま使い、小さな葉同士は1つの葉にまとめる
+    fn concat(left: Node, right: Node) -> Node {
+        if left.chars() == 0 {
+            return right;
+        }
+        if right.chars() == 0 {
+            return left;
+        }
+        match (left, right) {
+            (Node::Leaf { text: mut l, .. }, Node::Leaf { text: r, .. })
+                if l.chars().count() + r.chars().count() <= MAX_LEAF =>
+            {
+                l.push_str(&r);
+                Node::leaf(l)
+            }
+            (left, right) => Node::branch(left, right),
+        }
+    }
+
+    // `at`文字目の前で2つの木に分ける
+    fn split(self, at: usize) -> (Node, Node) {
+        match self {
+            Node::Leaf { mut text, .. } => {
+                let byte = byte_index(&text, at);
+                let right = text.split_off(byte);
+                (Node::leaf(text), Node::leaf(right))
+            }
+            Node::Branch { left, right, .. } => {
+                let left_chars = left.chars();
+                if at <= left_chars {
+                    let (ll, lr) = left.split(at);
+                    (ll, Node::concat(lr, *right))
+                } else {
+                    let (rl, rr) = right.split(at - left_chars);
+                    (Node::concat(*left, rl), rr)
+                }
+            }
+        }
+    }
+
+    fn char_at(&self, index: usize) -> Option<char> {
+        match self {
+            Node::Leaf { text, .. } => text.chars().nth(index),
+            Node::Branch { left, right, .. } => {
+                if index < left.chars() {
+                    left.char_at(index)
+                } else {
+                    right.char_at(index - left.chars())
+                }
+            }
+        }
+    }
+
+    // 先頭から`index`文字の中にある改行の数
+    fn newlines_before(&self, index: usize) -> usize {
+        match self {
+            Node::Leaf { text, .. } => text.chars().take(index).filter(|&c| c == '\n').count(),
+            Node::Branch { left, right, .. } => {
+                if index <= left.chars() {
+                    left.newlines_before(index)
+                } else {
+                    left.newlines() + right.newlines_before(index - left.chars())
+                }
+            }
+        }
+    }
+
+    // `n`個目（1から数える）の改行の直後の位置
+    fn after_newline(&self, n: usize) -> usize {
+        match self {
+            Node::Leaf { text, .. } => {
+                text.chars()
+                    .enumerate()
+                    .filter(|&(_, c)| c == '\n')
+                    .nth(n - 1)
+                    .map_or(0, |(i, _)| i)
+                    + 1
+            }
+            Node::Branch { left, right, .. } => {
+                if n <= left.newlines() {
+                    left.after_newline(n)
+                } else {
+                    left.chars() + right.after_newline(n - left.newlines())
+                }
+            }
+        }
+    }
+
+    // `range`の範囲の文字を`out`に書き足す
+    fn append_range(&self, range: Range<usize>, out: &mut String) {
+        if range.start >= range.end {
+            return;
+        }
+        match self {
+            Node::Leaf { text, .. } => {
+                let start = byte_index(text, range.start);
+                let end = byte_index(text, range.end);
+                out.push_str(&text[start..end]);
+            }
+            Node::Branch { left, right, .. } => {
+                let mid = left.chars();
+                left.append_range(range.start.min(mid)..range.end.min(mid), out);
+                right.append_range(range.start.max(mid) - mid..range.end.max(mid) - mid, out);
+            }
+        }
+    }
+
+    // 葉の文字列を左から順に`leaves`へ集める。隣り合う葉は`MAX_LEAF`文字を超えない範囲で1つにまとめる
+    // `last_chars`は`leaves`の最後の文字列の文字数
+    fn into_leaves(self, leaves: &mut ToyVec<String>, last_chars: &mut usize) {
+        match self {
+            Node::Leaf { text, chars, .. } => {
+                if chars == 0 {
+                    return;
+                }
+                match leaves.len().checked_sub(1) {
+                    Some(last) if *last_chars + chars <= MAX_LEAF => {
+                        leaves.as_mut_slice()[last].push_str(&text);
+                        *last_chars += chars;
+                    }
+                    _ => {
+                        leaves.push(text);
+                        *last_chars = chars;
+                    }
+                }
+            }
+            Node::Branch { left, right, .. } => {
+                left.into_leaves(leaves, last_chars);
+                right.into_leaves(leaves, last_chars);
+            }
+        }
+    }
+}
+
+// 文字の位置をバイトの位置に変換する
+fn byte_index(text: &str, chars: usize) -> usize {
+    text.char_indices()
+        .nth(chars)
+        .map_or(text.len(), |(i, _)| i)
+}
+
+// 葉を左右半分ずつに分けて、平衡な木を作る
+fn build(leaves: &mut [String]) -> Node {
+    match leaves.len() {
+        0 => Node::empty(),
+        1 => Node::leaf(std::mem::take(&mut leaves[0])),
+        n => {
+            let (left, right) = leaves.split_at_mut(n / 2);
+            Node::branch(build(left), build(right))
+        }
+    }
+}
+
+// 大きな文書を扱うためのロープ
+// テキストを短い文字列（葉）に分けて二分木に並べるので、文書の途中への挿入や削除は、
+// 文書全体をずらす代わりに木の分割と連結で済む
+// 葉は`ToyVec<u8>`ではなく`String`で持つ。UTF-8として正しいことを型で保証でき、
+// 文字単位の分割や`&str`としての書き出しをそのまま使えるため
+// 位置と行は文字（`char`）単位で数え、行と列はどちらも0から数える
+pub struct ToyRope {
+    root: Node,
+    cursor: usize,
+}
+
+impl ToyRope {
+    pub fn new() -> Self {
+        Self {
+            root: Node::empty(),
+            cursor: 0,
+        }
+    }
+
+    pub fn from_text(text: &str) -> Self {
+        let mut leaves = ToyVec::new();
+        let mut leaf = String::new();
+        for (i, c) in text.chars().enumerate() {
+            if i > 0 && i % MAX_LEAF == 0 {
+                leaves.push(std::mem::take(&mut leaf));
+            }
+            leaf.push(c);
+        }
+        leaves.push(leaf);
+        Self {
+            root: build(leaves.as_mut_slice()),
+            cursor: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.root.chars()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn char_at(&self, index: usize) -> Option<char> {
+        self.root.char_at(index)
+    }
+
+    pub fn chars(&self) -> Chars<'_> {
+        Chars {
+            stack: vec![&self.root],
+            current: "".chars(),
+        }
+    }
+
+    // `range`の範囲の文字列を返す
+    pub fn slice(&self, range: Range<usize>) -> String {
+        self.check_range(&range);
+        let mut out = String::new();
+        self.root.append_range(range, &mut out);
+        out
+    }
+
+    // `index`の位置に文字列を挿入する
+    pub fn insert(&mut self, index: usize, text: &str) {
+        assert!(
+            index <= self.len(),
+            "insertion index (is {}) should be <= len (is {})",
+            index,
+            self.len()
+        );
+        let inserted = ToyRope::from_text(text).root;
+        let count = inserted.chars();
+        let (left, right) = self.take_root().split(index);
+        self.set_root(Node::concat(Node::concat(left, inserted), right));
+        if self.cursor >= index {
+            self.cursor += count;
+        }
+    }
+
+    // `range`の範囲の文字を削除する
+    pub fn remove(&mut self, range: Range<usize>) {
+        self.check_range(&range);
+        let (rest, right) = self.take_root().split(range.end);
+        let (left, _) = rest.split(range.start);
+        self.set_root(Node::concat(left, right));
+        if self.cursor >= range.end {
+            self.cursor -= range.end - range.start;
+        } else if self.cursor > range.start {
+            self.cursor = range.start;
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.root.newlines() + 1
+    }
+
+    // `pos`番目の文字の(行, 列)を返す
+    pub fn line_col(&self, pos: usize) -> (usize, usize) {
+        let pos = pos.min(self.len());
+        let line = self.root.newlines_before(pos);
+        let start = self.line_start(line).unwrap_or(0);
+        (line, pos - start)
+    }
+
+    // `line`行目の先頭の位置を返す
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        match line {
+            0 => Some(0),
+            n if n <= self.root.newlines() => Some(self.root.after_newline(n)),
+            _ => None,
+        }
+    }
+
+    // `line`行目の文字数（改行を除く）を返す
+    pub fn line_len(&self, line: usize) -> Option<usize> {
+        let start = self.line_start(line)?;
+        let end = self
+            .line_start(line + 1)
+            .map_or(self.len(), |next| next - 1);
+        Some(end - start)
+    }
+
+    // `line`行目の内容を返す（改行を除く）
+    pub fn line(&self, line: usize) -> Option<String> {
+        let start = self.line_start(line)?;
+        Some(self.slice(start..start + self.line_len(line)?))
+    }
+
+    // (行, 列)を文字の位置に変換する。列は行末（改行の直前）まで指定できる
+    pub fn position(&self, line: usize, col: usize) -> Option<usize> {
+        if col > self.line_len(line)? {
+            return None;
+        }
+        Some(self.line_start(line)? + col)
+    }
+
+    // カーソルの位置。`insert_str`や`delete_backward`はこの位置で編集する
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn move_to(&mut self, pos: usize) {
+        assert!(
+            pos <= self.len(),
+            "cursor position (is {}) should be <= len (is {})",
+            pos,
+            self.len()
+        );
+        self.cursor = pos;
+    }
+
+    pub fn move_left(&mut self) -> bool {
+        if self.cursor == 0 {
+            return false;
+        }
+        self.cursor -= 1;
+        true
+    }
+
+    pub fn move_right(&mut self) -> bool {
+        if self.cursor == self.len() {
+            return false;
+        }
+        self.cursor += 1;
+        true
+    }
+
+    // カーソルを(行, 列)へ動かす。範囲外なら`false`を返す
+    pub fn move_to_line_col(&mut self, line: usize, col: usize) -> bool {
+        match self.position(line, col) {
+            Some(pos) => {
+                self.cursor = pos;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // カーソルを上の行の同じ列へ動かす。上の行が短ければ行末へ動かす
+    pub fn move_up(&mut self) -> bool {
+        let (line, col) = self.line_col(self.cursor);
+        if line == 0 {
+            return false;
+        }
+        self.move_to_column(line - 1, col)
+    }
+
+    // カーソルを下の行の同じ列へ動かす。下の行が短ければ行末へ動かす
+    pub fn move_down(&mut self) -> bool {
+        let (line, col) = self.line_col(self.cursor);
+        self.move_to_column(line + 1, col)
+    }
+
+    // カーソルの位置に文字列を挿入し、カーソルを挿入した文字列の後ろへ進める
+    pub fn insert_str(&mut self, text: &str) {
+        self.insert(self.cursor, text);
+    }
+
+    // カーソルの直前の文字を削除する（バックスペース）
+    pub fn delete_backward(&mut self) -> Option<char> {
+        let c = self.char_at(self.cursor.checked_sub(1)?)?;
+        self.remove(self.cursor - 1..self.cursor);
+        Some(c)
+    }
+
+    fn move_to_column(&mut self, line: usize, col: usize) -> bool {
+        match self.line_len(line) {
+            Some(len) => self.move_to_line_col(line, col.min(len)),
+            None => false,
+        }
+    }
+
+    fn check_range(&self, range: &Range<usize>) {
+        assert!(
+            range.start <= range.end && range.end <= self.len(),
+            "range {:?} out of bounds for length {}",
+            range,
+            self.len()
+        );
+    }
+
+    fn take_root(&mut self) -> Node {
+        std::mem::replace(&mut self.root, Node::empty())
+    }
+
+    // 編集を重ねて木が深くなりすぎたら、葉を集めて平衡な木に作り直す
+    // 少しずつ挿入してできた短い葉は、このときにまとめる
+    fn set_root(&mut self, root: Node) {
+        self.root = if root.depth() > MAX_DEPTH {
+            let mut leaves = ToyVec::new();
+            root.into_leaves(&mut leaves, &mut 0);
+            build(leaves.as_mut_slice())
+        } else {
+            root
+        };
+    }
+}
+
+impl Default for ToyRope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for ToyRope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut stack = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            match node {
+                Node::Leaf { text, .. } => f.write_str(text)?,
+                Node::Branch { left, right, .. } => {
+                    stack.push(right);
+                    stack.push(left);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// 先頭から順に文字を返すイテレータ。葉を左から順にたどる
+pub struct Chars<'rope> {
+    // これからたどる部分木。末尾が次にたどる部分木
+    stack: Vec<&'rope Node>,
+    current: std::str::Chars<'rope>,
+}
+
+impl<'rope> Iterator for Chars<'rope> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(c) = self.current.next() {
+                return Some(c);
+            }
+            match self.stack.pop()? {
+                Node::Leaf { text, .. } => self.current = text.chars(),
+                Node::Branch { left, right, .. } => {
+                    self.stack.push(right);
+                    self.stack.push(left);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Node, ToyRope, MAX_DEPTH, MAX_LEAF};
+
+    fn leaf_count(node: &Node) -> usize {
+        match node {
+            Node::Leaf { .. } => 1,
+            Node::Branch { left, right, .. } => leaf_count(left) + leaf_count(right),
+        }
+    }
+
+    #[test]
+    fn test_insert_remove_and_slice() {
+        let text: String = "あいうえお".repeat(MAX_LEAF);
+        let mut rope = ToyRope::from_text(&text);
+        assert_eq!(rope.len(), 5 * MAX_LEAF);
+        assert_eq!(rope.char_at(7), Some('う'));
+
+        rope.insert(3, "ABC");
+        assert_eq!(rope.slice(0..8), "あいうABCえお");
+        rope.remove(1..5);
+        assert_eq!(rope.slice(0..4), "あCえお");
+        assert_eq!(rope.len(), 5 * MAX_LEAF - 1);
+        assert_eq!(rope.chars().count(), rope.len());
+        assert!(rope.chars().eq(rope.to_string().chars()));
+    }
+
+    #[test]
+    fn test_stays_balanced() {
+        let mut rope = ToyRope::new();
+        for i in 0..2000 {
+            rope.insert(rope.len() / 2, &i.to_string());
+        }
+        assert!(rope.root.depth() <= MAX_DEPTH);
+    }
+
+    #[test]
+    fn test_merges_small_leaves() {
+        // 1文字ずつ挿入しても、1文字の葉が文字の数だけ残ることはない
+        let mut rope = ToyRope::new();
+        for i in 0..5000 {
+            let c = char::from(b'a' + (i % 26) as u8);
+            rope.insert(i * 7 % (rope.len() + 1), &c.to_string());
+        }
+        assert_eq!(rope.len(), 5000);
+        let leaves = leaf_count(&rope.root);
+        assert!(
+            leaves <= 2 * rope.len() / MAX_LEAF + 3 * MAX_DEPTH,
+            "too many leaves: {}",
+            leaves
+        );
+    }
+
+    #[test]
+    fn test_lines_and_cursor_movement() {
+        let mut rope = ToyRope::from_text("うさぎ\nはねる\n\nおわり");
+        assert_eq!(rope.line_count(), 4);
+        assert_eq!(rope.line(1).as_deref(), Some("はねる"));
+        assert_eq!(rope.line(3).as_deref(), Some("おわり"));
+        assert_eq!(rope.line(4), None);
+        assert_eq!(rope.line_col(5), (1, 1));
+        assert_eq!(rope.position(3, 3), Some(rope.len()));
+
+        assert!(rope.move_to_line_col(0, 2));
+        assert!(rope.move_down());
+        assert!(rope.move_down());
+        assert_eq!(rope.line_col(rope.cursor()), (2, 0));
+        assert!(rope.move_down());
+        assert!(!rope.move_down());
+
+        rope.insert_str("ぴょん");
+        assert_eq!(rope.delete_backward(), Some('ん'));
+        assert_eq!(rope.to_string(), "うさぎ\nはねる\n\nぴょおわり");
+        assert_eq!(rope.line_col(rope.cursor()), (3, 2));
+    }
+}
@@ -0,0 +1,242 @@
+// 登録したジョブ（一度だけ呼べるクロージャ）を決まった順序で実行するスケジューラ
+// ジョブは`FnOnce`なので、環境から取り込んだ値を消費するクロージャも登録できる
+use crate::adapters::{ToyQueue, ToyStack};
+use crate::ToyVec;
+
+// ジョブを実行する順序
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    // 最後に登録したものから
+    Lifo,
+    // 最初に登録したものから
+    Fifo,
+    // 優先度の高いものから。同じ優先度なら登録した順
+    Priority,
+}
+
+type Job<'a, R> = Box<dyn FnOnce() -> R + 'a>;
+
+// `ToyVec`に入れるため`Default`を実装する。ジョブは取り出すまで`Some`
+struct Entry<'a, R> {
+    priority: u32,
+    seq: u64,
+    job: Option<Job<'a, R>>,
+}
+
+impl<'a, R> Default for Entry<'a, R> {
+    fn default() -> Self {
+        Self {
+            priority: 0,
+            seq: 0,
+            job: None,
+        }
+    }
+}
+
+impl<'a, R> Entry<'a, R> {
+    // `other`より先に実行すべきなら`true`
+    fn runs_before(&self, other: &Self) -> bool {
+        (self.priority, other.seq) > (other.priority, self.seq)
+    }
+}
+
+enum Jobs<'a, R> {
+    Stack(ToyStack<Entry<'a, R>>),
+    Queue(ToyQueue<Entry<'a, R>>),
+    // 優先度の二分ヒープ。`heap[0]`が次に実行するジョブ
+    Heap(ToyVec<Entry<'a, R>>),
+}
+
+pub struct WorkScheduler<'a, R = ()> {
+    jobs: Jobs<'a, R>,
+    order: Order,
+    capacity: usize,
+    len: usize,
+    next_seq: u64,
+}
+
+impl<'a, R> WorkScheduler<'a, R> {
+    // 最大で`capacity`個のジョブを保持できるスケジューラを作る
+    pub fn new(order: Order, capacity: usize) -> Self {
+        let jobs = match order {
+            Order::Lifo => Jobs::Stack(ToyStack::with_capacity(capacity)),
+            Order::Fifo => Jobs::Queue(ToyQueue::with_capacity(capacity)),
+            Order::Priority => Jobs::Heap(ToyVec::with_capacity(capacity)),
+        };
+        Self {
+            jobs,
+            order,
+            capacity,
+            len: 0,
+            next_seq: 0,
+        }
+    }
+
+    pub fn order(&self) -> Order {
+        self.order
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // まだ実行していないジョブの数
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // 優先度0でジョブを登録する
+    // 満杯なら登録せず、ジョブを`Err`で呼び出し側へ返す（`OverflowPolicy::Reject`と同じ扱い）
+    pub fn submit<F>(&mut self, job: F) -> Result<(), F>
+    where
+        F: FnOnce() -> R + 'a,
+    {
+        self.submit_with_priority(0, job)
+    }
+
+    // 優先度を付けてジョブを登録する。優先度は`Order::Priority`のときだけ使う
+    pub fn submit_with_priority<F>(&mut self, priority: u32, job: F) -> Result<(), F>
+    where
+        F: FnOnce() -> R + 'a,
+    {
+        if self.len == self.capacity {
+            return Err(job);
+        }
+        let entry = Entry {
+            priority,
+            seq: self.next_seq,
+            job: Some(Box::new(job)),
+        };
+        self.next_seq += 1;
+        self.len += 1;
+        match &mut self.jobs {
+            Jobs::Stack(stack) => stack.push(entry),
+            Jobs::Queue(queue) => queue.push_back(entry),
+            Jobs::Heap(heap) => {
+                heap.push(entry);
+                sift_up(heap.as_mut_slice());
+            }
+        }
+        Ok(())
+    }
+
+    // 次のジョブを取り出して実行し、その結果を返す。ジョブがなければ`None`
+    pub fn run_next(&mut self) -> Option<R> {
+        let entry = match &mut self.jobs {
+            Jobs::Stack(stack) => stack.pop(),
+            Jobs::Queue(queue) => queue.pop_front(),
+            Jobs::Heap(heap) => {
+                let last = heap.len().checked_sub(1)?;
+                heap.as_mut_slice().swap(0, last);
+                let entry = heap.pop();
+                sift_down(heap.as_mut_slice());
+                entry
+            }
+        }?;
+        self.len -= 1;
+        // 登録したジョブは必ず`Some`
+        let job = entry.job?;
+        Some(job())
+    }
+
+    // すべてのジョブを実行し、実行した順に結果を返す
+    // 実行中に登録されたジョブはないので（`&mut self`を借りているため）、呼び出し時点のジョブだけが実行される
+    pub fn run_all(&mut self) -> Vec<R> {
+        let mut results = Vec::with_capacity(self.len);
+        while let Some(result) = self.run_next() {
+            results.push(result);
+        }
+        results
+    }
+}
+
+// 末尾の要素をヒープの正しい位置まで上げる
+fn sift_up<R>(heap: &mut [Entry<'_, R>]) {
+    let mut i = heap.len() - 1;
+    while i > 0 {
+        let parent = (i - 1) / 2;
+        if !heap[i].runs_before(&heap[parent]) {
+            break;
+        }
+        heap.swap(i, parent);
+        i = parent;
+    }
+}
+
+// 先頭の要素をヒープの正しい位置まで下げる
+fn sift_down<R>(heap: &mut [Entry<'_, R>]) {
+    let mut i = 0;
+    loop {
+        let mut first = i;
+        for child in [2 * i + 1, 2 * i + 2] {
+            if child < heap.len() && heap[child].runs_before(&heap[first]) {
+                first = child;
+            }
+        }
+        if first == i {
+            break;
+        }
+        heap.swap(i, first);
+        i = first;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Order, WorkScheduler};
+    use std::cell::RefCell;
+
+    fn run_in(order: Order) -> Vec<&'static str> {
+        let mut scheduler = WorkScheduler::new(order, 8);
+        for (priority, name) in [(1, "a"), (3, "b"), (1, "c"), (2, "d")] {
+            assert!(scheduler
+                .submit_with_priority(priority, move || name)
+                .is_ok());
+        }
+        scheduler.run_all()
+    }
+
+    #[test]
+    fn test_orders() {
+        assert_eq!(run_in(Order::Lifo), ["d", "c", "b", "a"]);
+        assert_eq!(run_in(Order::Fifo), ["a", "b", "c", "d"]);
+        assert_eq!(run_in(Order::Priority), ["b", "d", "a", "c"]);
+    }
+
+    #[test]
+    fn test_capacity_and_borrowed_environment() {
+        let log = RefCell::new(Vec::new());
+        let report = String::from("report");
+        {
+            // `move`クロージャにも参照だけを取り込ませる
+            let log = &log;
+            let mut scheduler = WorkScheduler::new(Order::Fifo, 2);
+            // 環境を借用するクロージャ（`Fn`）
+            assert!(scheduler.submit(|| log.borrow_mut().push(1)).is_ok());
+            // 環境から取り込んだ値を消費するクロージャ（`FnOnce`）
+            assert!(scheduler
+                .submit(move || log.borrow_mut().push(report.into_bytes().len()))
+                .is_ok());
+            // 満杯なら、登録できなかったジョブがそのまま返ってくる
+            let rejected = scheduler.submit(|| log.borrow_mut().push(0)).err();
+            assert!(rejected.is_some());
+
+            assert_eq!(scheduler.run_next(), Some(()));
+            assert_eq!(scheduler.len(), 1);
+            assert!(scheduler.submit(|| log.borrow_mut().push(3)).is_ok());
+            assert_eq!(scheduler.run_all().len(), 2);
+            assert!(scheduler.is_empty());
+            assert_eq!(scheduler.run_next(), None);
+
+            // 返ってきたジョブは呼び出し側で実行できる
+            if let Some(job) = rejected {
+                job();
+            }
+        }
+        assert_eq!(log.into_inner(), [1, 6, 3, 0]);
+    }
+}
@@ -0,0 +1,87 @@
+// `serde`フィーチャーを有効にすると使える、`ToyVec`の`Serialize`と`Deserialize`の実装
+// `ToyVec`は要素の並び（シーケンス）として読み書きするので、`Vec`と同じ形式になる
+use crate::codec::MAX_PREALLOC;
+use crate::ToyVec;
+use serde::de::{Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeSeq, Serializer};
+use std::fmt;
+use std::marker::PhantomData;
+
+impl<T: Serialize + Default> Serialize for ToyVec<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for elem in self.iter() {
+            seq.serialize_element(elem)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Default> Deserialize<'de> for ToyVec<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(ToyVecVisitor(PhantomData))
+    }
+}
+
+struct ToyVecVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de> + Default> Visitor<'de> for ToyVecVisitor<T> {
+    type Value = ToyVec<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a sequence")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let capacity = seq.size_hint().unwrap_or(0).min(MAX_PREALLOC);
+        let mut v = ToyVec::with_capacity(capacity);
+        while let Some(elem) = seq.next_element()? {
+            v.push(elem);
+        }
+        Ok(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ToyVec;
+    use serde_test::{assert_de_tokens, assert_tokens, Token};
+
+    #[test]
+    fn test_round_trip() {
+        let mut v = ToyVec::new();
+        v.push("ネザーランドドワーフ".to_string());
+        v.push("ホーランドロップ".to_string());
+        assert_tokens(
+            &v,
+            &[
+                Token::Seq { len: Some(2) },
+                Token::Str("ネザーランドドワーフ"),
+                Token::Str("ホーランドロップ"),
+                Token::SeqEnd,
+            ],
+        );
+
+        let empty: ToyVec<u32> = ToyVec::new();
+        assert_tokens(&empty, &[Token::Seq { len: Some(0) }, Token::SeqEnd]);
+    }
+
+    #[test]
+    fn test_untrusted_size_hint() {
+        // 要素数のヒントが実際より大きくても、巨大な領域は確保せずに読み込める
+        let mut v = ToyVec::new();
+        v.push(1u8);
+        assert_de_tokens(
+            &v,
+            &[
+                Token::Seq {
+                    len: Some(usize::MAX),
+                },
+                Token::U8(1),
+                Token::SeqEnd,
+            ],
+        );
+        // ヒントがなくても読み込める
+        assert_de_tokens(&v, &[Token::Seq { len: None }, Token::U8(1), Token::SeqEnd]);
+    }
+}
@@ -0,0 +1,178 @@
+// 参照カウントで領域を共有する、コピーオンライトの`ToyVec`
+// `Clone`は参照カウントを増やすだけなのでO(1)。変更するときに他の所有者がいれば、
+// そのときだけ領域を複製してから変更する（`Rc::get_mut`が`None`を返す場合）
+use crate::ToyVec;
+use std::fmt;
+use std::ops::Deref;
+use std::rc::Rc;
+use std::sync::Arc;
+
+// `Rc`版と`Arc`版で同じ実装を使う
+macro_rules! shared_toy_vec {
+    ($name:ident, $ptr:ident) => {
+        pub struct $name<T> {
+            inner: $ptr<ToyVec<T>>,
+        }
+
+        impl<T: Clone + Default> $name<T> {
+            pub fn new() -> Self {
+                Self::from(ToyVec::new())
+            }
+
+            // 要素を変更するための可変の参照を返す
+            // 共同所有者がいなければそのまま、いれば領域を複製して自分だけの所有にしてから返す
+            pub fn make_mut(&mut self) -> &mut ToyVec<T> {
+                if $ptr::get_mut(&mut self.inner).is_none() {
+                    // 参照カウントが2以上なので、複製した`ToyVec`に差し替える
+                    // 元の領域は他の所有者が引き続き使う
+                    self.inner = $ptr::new((*self.inner).clone());
+                }
+                // 差し替えた直後は参照カウントが1なので、必ず`Some`になる
+                $ptr::get_mut(&mut self.inner).unwrap()
+            }
+
+            pub fn push(&mut self, element: T) {
+                self.make_mut().push(element);
+            }
+
+            pub fn pop(&mut self) -> Option<T> {
+                // 空なら複製せずに済ませる
+                if self.inner.is_empty() {
+                    return None;
+                }
+                self.make_mut().pop()
+            }
+
+            // 中身の`ToyVec`を取り出す。共同所有者がいれば複製したものを返す
+            pub fn into_inner(self) -> ToyVec<T> {
+                $ptr::try_unwrap(self.inner).unwrap_or_else(|shared| (*shared).clone())
+            }
+        }
+
+        impl<T> $name<T> {
+            // 2つのベクタが同じ領域を共有していれば`true`を返す
+            pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+                $ptr::ptr_eq(&this.inner, &other.inner)
+            }
+
+            // 領域を共有している所有者の数を返す
+            pub fn strong_count(this: &Self) -> usize {
+                $ptr::strong_count(&this.inner)
+            }
+        }
+
+        // `Clone`は領域を共有するだけで、要素は複製しない
+        impl<T> Clone for $name<T> {
+            fn clone(&self) -> Self {
+                Self {
+                    inner: $ptr::clone(&self.inner),
+                }
+            }
+        }
+
+        // 読み取りは共有している`ToyVec`のメソッドをそのまま使える
+        impl<T> Deref for $name<T> {
+            type Target = ToyVec<T>;
+
+            fn deref(&self) -> &ToyVec<T> {
+                &self.inner
+            }
+        }
+
+        impl<T> From<ToyVec<T>> for $name<T> {
+            fn from(vec: ToyVec<T>) -> Self {
+                Self {
+                    inner: $ptr::new(vec),
+                }
+            }
+        }
+
+        impl<T: Clone + Default> Default for $name<T> {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl<T: PartialEq> PartialEq for $name<T> {
+            fn eq(&self, other: &Self) -> bool {
+                // 同じ領域を共有していれば要素を比べるまでもない
+                $ptr::ptr_eq(&self.inner, &other.inner) || *self.inner == *other.inner
+            }
+        }
+
+        impl<T: fmt::Debug> fmt::Debug for $name<T> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                self.inner.fmt(f)
+            }
+        }
+    };
+}
+
+// 1つのスレッドの中で共有する`ToyVec`
+shared_toy_vec!(RcToyVec, Rc);
+
+// スレッドをまたいで共有できる`ToyVec`。参照カウントの操作はアトミックに行う
+shared_toy_vec!(ArcToyVec, Arc);
+
+#[cfg(test)]
+mod tests {
+    use super::{ArcToyVec, RcToyVec};
+    use crate::ToyVec;
+    use std::thread;
+
+    fn toy_vec(n: i32) -> ToyVec<i32> {
+        let mut v = ToyVec::new();
+        for i in 0..n {
+            v.push(i);
+        }
+        v
+    }
+
+    #[test]
+    fn test_rc_copy_on_write() {
+        let mut a = RcToyVec::from(toy_vec(1000));
+        let b = a.clone();
+        // 複製しても領域は共有したまま
+        assert!(RcToyVec::ptr_eq(&a, &b));
+        assert_eq!(RcToyVec::strong_count(&a), 2);
+
+        // 最初の変更で`a`だけが複製される
+        a.push(1000);
+        assert!(!RcToyVec::ptr_eq(&a, &b));
+        assert_eq!(RcToyVec::strong_count(&a), 1);
+        assert_eq!(RcToyVec::strong_count(&b), 1);
+        assert_eq!(a.len(), 1001);
+        assert_eq!(b.len(), 1000);
+
+        // 所有者が1人になれば、変更しても複製しない
+        let before = a.as_slice().as_ptr();
+        *a.make_mut().get_mut(0).unwrap() = -1;
+        assert_eq!(a.as_slice().as_ptr(), before);
+        assert_eq!(a.get(0), Some(&-1));
+        assert_eq!(b.get(0), Some(&0));
+
+        let c = b.clone();
+        assert_eq!(b.into_inner(), toy_vec(1000));
+        assert_eq!(c.into_inner(), toy_vec(1000));
+    }
+
+    #[test]
+    fn test_arc_shared_between_threads() {
+        let shared = ArcToyVec::from(toy_vec(100));
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let mut local = shared.clone();
+                thread::spawn(move || {
+                    // スレッドの中で変更しても、他のスレッドの`ToyVec`には影響しない
+                    local.push(i);
+                    local.iter().sum::<i32>()
+                })
+            })
+            .collect();
+        for (i, handle) in handles.into_iter().enumerate() {
+            assert_eq!(handle.join().unwrap(), 4950 + i as i32);
+        }
+        assert_eq!(ArcToyVec::strong_count(&shared), 1);
+        assert_eq!(shared.len(), 100);
+    }
+}
@@ -0,0 +1,579 @@
+use crate::codec::{Decode, DecodeError, Decoder, Encode, Encoder};
+use crate::ToyVec;
+
+// スロットマップが返すハンドル。スロットの位置と世代番号の組で要素を特定する
+// 要素が削除されるとスロットの世代が進むので、古いキーでのアクセスは`None`になる
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Key {
+    index: usize,
+    generation: u64,
+}
+
+impl Key {
+    // キーが指すスロットの位置
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    // キーが作られたときのスロットの世代
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+// `ToySlotMap`のスロット。要素があれば`value`が`Some`になる
+// 空きスロットは`next_free`で次の空きスロットを指し、フリーリストを作る
+struct Slot<T> {
+    value: Option<T>,
+    generation: u64,
+    next_free: Option<usize>,
+}
+
+// `ToyVec<T>`は`T: Default`を要求するので、`T`に関係なく`Default`を実装しておく
+impl<T> Default for Slot<T> {
+    fn default() -> Self {
+        Self {
+            value: None,
+            generation: 0,
+            next_free: None,
+        }
+    }
+}
+
+// 世代付きのキーで要素を管理するコンテナ。挿入・削除・取得はすべてO(1)
+// `ToyVec`のインデックスと違い、要素を削除しても他の要素のキーは変わらない
+pub struct ToySlotMap<T> {
+    slots: ToyVec<Slot<T>>,
+    // フリーリストの先頭（再利用できるスロット）
+    free_head: Option<usize>,
+    len: usize,
+}
+
+impl<T> ToySlotMap<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            slots: ToyVec::with_capacity(capacity),
+            free_head: None,
+            len: 0,
+        }
+    }
+
+    // 格納されている要素数を返す
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // 要素を追加し、その要素を指すキーを返す
+    pub fn insert(&mut self, value: T) -> Key {
+        self.len += 1;
+        match self.free_head {
+            // 空きスロットがあれば再利用する
+            Some(index) => {
+                let slot = self.slots.get_mut(index).expect("free list is corrupted");
+                self.free_head = slot.next_free.take();
+                slot.value = Some(value);
+                Key {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            // なければ末尾にスロットを追加する
+            None => {
+                let index = self.slots.len();
+                self.slots.push(Slot {
+                    value: Some(value),
+                    generation: 0,
+                    next_free: None,
+                });
+                Key {
+                    index,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    // キーが指す要素を取り除いて返す。キーが古ければ`None`を返す
+    pub fn remove(&mut self, key: Key) -> Option<T> {
+        let slot = self.slots.get_mut(key.index)?;
+        if slot.generation != key.generation || slot.value.is_none() {
+            return None;
+        }
+        let value = slot.value.take();
+        // 世代を進めて、このスロットを指す古いキーを無効にする
+        slot.generation = slot.generation.wrapping_add(1);
+        slot.next_free = self.free_head;
+        self.free_head = Some(key.index);
+        self.len -= 1;
+        value
+    }
+
+    pub fn get(&self, key: Key) -> Option<&T> {
+        let slot = self.slots.get(key.index)?;
+        if slot.generation == key.generation {
+            slot.value.as_ref()
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, key: Key) -> Option<&mut T> {
+        let slot = self.slots.get_mut(key.index)?;
+        if slot.generation == key.generation {
+            slot.value.as_mut()
+        } else {
+            None
+        }
+    }
+
+    pub fn contains_key(&self, key: Key) -> bool {
+        self.get(key).is_some()
+    }
+
+    // 全要素を削除する。以前に発行したキーはすべて無効になる
+    pub fn clear(&mut self) {
+        for index in 0..self.slots.len() {
+            let key = match self.slots.get(index) {
+                Some(slot) if slot.value.is_some() => Key {
+                    index,
+                    generation: slot.generation,
+                },
+                _ => continue,
+            };
+            self.remove(key);
+        }
+    }
+
+    // (キー, 要素への参照)を返すイテレータを作る。空きスロットは飛ばす
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            slots: self.slots.as_slice(),
+            pos: 0,
+        }
+    }
+
+    // (キー, 要素への可変の参照)を返すイテレータを作る
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            slots: self.slots.as_mut_slice().iter_mut().enumerate(),
+        }
+    }
+}
+
+impl<T> Default for ToySlotMap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Iter<'a, T> {
+    slots: &'a [Slot<T>],
+    pos: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (Key, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pos < self.slots.len() {
+            let index = self.pos;
+            let slot = &self.slots[index];
+            self.pos += 1;
+            if let Some(value) = &slot.value {
+                let key = Key {
+                    index,
+                    generation: slot.generation,
+                };
+                return Some((key, value));
+            }
+        }
+        None
+    }
+}
+
+pub struct IterMut<'a, T> {
+    slots: std::iter::Enumerate<std::slice::IterMut<'a, Slot<T>>>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = (Key, &'a mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (index, slot) in &mut self.slots {
+            if let Some(value) = slot.value.as_mut() {
+                let key = Key {
+                    index,
+                    generation: slot.generation,
+                };
+                return Some((key, value));
+            }
+        }
+        None
+    }
+}
+
+//
+// 要素を隙間なく並べて持つスロットマップ。イテレーションが多い用途向け
+//
+
+// `DenseSlotMap`のスロット。要素そのものではなく`values`内の位置を持つ
+#[derive(Default)]
+struct DenseSlot {
+    // 要素があれば`values`内の位置、なければ次の空きスロット
+    dense_index: Option<usize>,
+    next_free: Option<usize>,
+    generation: u64,
+}
+
+// 要素は`values`に詰めて格納するので、イテレーションは`ToyVec`を走査するだけで済む
+// その代わり、取得はスロットを経由する分だけ間接参照が1段増える
+pub struct DenseSlotMap<T> {
+    slots: ToyVec<DenseSlot>,
+    // `values[i]`を指すスロットの位置
+    slot_indices: ToyVec<usize>,
+    values: ToyVec<T>,
+    free_head: Option<usize>,
+}
+
+impl<T: Default> DenseSlotMap<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            slots: ToyVec::with_capacity(capacity),
+            slot_indices: ToyVec::with_capacity(capacity),
+            values: ToyVec::with_capacity(capacity),
+            free_head: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn insert(&mut self, value: T) -> Key {
+        let dense_index = self.values.len();
+        let index = match self.free_head {
+            Some(index) => {
+                let slot = self.slots.get_mut(index).expect("free list is corrupted");
+                self.free_head = slot.next_free.take();
+                slot.dense_index = Some(dense_index);
+                index
+            }
+            None => {
+                self.slots.push(DenseSlot {
+                    dense_index: Some(dense_index),
+                    next_free: None,
+                    generation: 0,
+                });
+                self.slots.len() - 1
+            }
+        };
+        self.values.push(value);
+        self.slot_indices.push(index);
+        self.key_of(index)
+    }
+
+    pub fn remove(&mut self, key: Key) -> Option<T> {
+        let dense_index = self.dense_index(key)?;
+        let slot = self.slots.get_mut(key.index)?;
+        slot.dense_index = None;
+        slot.generation = slot.generation.wrapping_add(1);
+        slot.next_free = self.free_head;
+        self.free_head = Some(key.index);
+
+        // 末尾の要素を空いた位置へ移し、移した要素のスロットを付け替える
+        let value = self.values.swap_remove(dense_index);
+        self.slot_indices.swap_remove(dense_index);
+        if let Some(&moved) = self.slot_indices.get(dense_index) {
+            if let Some(slot) = self.slots.get_mut(moved) {
+                slot.dense_index = Some(dense_index);
+            }
+        }
+        value
+    }
+
+    pub fn get(&self, key: Key) -> Option<&T> {
+        let dense_index = self.dense_index(key)?;
+        self.values.get(dense_index)
+    }
+
+    pub fn get_mut(&mut self, key: Key) -> Option<&mut T> {
+        let dense_index = self.dense_index(key)?;
+        self.values.get_mut(dense_index)
+    }
+
+    pub fn contains_key(&self, key: Key) -> bool {
+        self.dense_index(key).is_some()
+    }
+
+    // 全要素を格納順に並べたスライス
+    pub fn values(&self) -> &[T] {
+        self.values.as_slice()
+    }
+
+    pub fn values_mut(&mut self) -> &mut [T] {
+        self.values.as_mut_slice()
+    }
+
+    // (キー, 要素への参照)を返すイテレータを作る
+    pub fn iter(&self) -> impl Iterator<Item = (Key, &T)> + '_ {
+        self.slot_indices
+            .iter()
+            .zip(self.values.iter())
+            .map(move |(&index, value)| (self.key_of(index), value))
+    }
+
+    fn key_of(&self, index: usize) -> Key {
+        Key {
+            index,
+            generation: self.slots.get(index).map_or(0, |s| s.generation),
+        }
+    }
+
+    // キーが有効なら`values`内の位置を返す
+    fn dense_index(&self, key: Key) -> Option<usize> {
+        let slot = self.slots.get(key.index)?;
+        if slot.generation == key.generation {
+            slot.dense_index
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: Default> Default for DenseSlotMap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//
+// バイナリ形式（`codec`）の実装
+//
+// キーが読み込んだ後も使えるよう、空きスロットも含めて各スロットの世代を書く
+// フリーリストは書かず、読み込むときに位置の小さい空きスロットから再利用するよう作り直す
+//
+
+impl<T: Encode> Encode for Slot<T> {
+    fn encode(&self, enc: &mut Encoder) {
+        self.generation.encode(enc);
+        self.value.encode(enc);
+    }
+}
+
+impl<T: Decode> Decode for Slot<T> {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        Ok(Self {
+            generation: u64::decode(dec)?,
+            value: Option::decode(dec)?,
+            next_free: None,
+        })
+    }
+}
+
+impl<T: Encode> Encode for ToySlotMap<T> {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.write_seq(self.slots.len(), self.slots.iter());
+    }
+}
+
+impl<T: Decode> Decode for ToySlotMap<T> {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let mut map = Self {
+            slots: dec.read_seq()?,
+            free_head: None,
+            len: 0,
+        };
+        for index in (0..map.slots.len()).rev() {
+            if let Some(slot) = map.slots.get_mut(index) {
+                if slot.value.is_some() {
+                    map.len += 1;
+                } else {
+                    slot.next_free = map.free_head;
+                    map.free_head = Some(index);
+                }
+            }
+        }
+        Ok(map)
+    }
+}
+
+// `DenseSlotMap`のスロットは世代だけを書く。要素の位置は`slot_indices`から復元する
+impl Encode for DenseSlot {
+    fn encode(&self, enc: &mut Encoder) {
+        self.generation.encode(enc);
+    }
+}
+
+impl Decode for DenseSlot {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        Ok(Self {
+            generation: u64::decode(dec)?,
+            ..Self::default()
+        })
+    }
+}
+
+impl<T: Encode + Default> Encode for DenseSlotMap<T> {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.write_seq(self.slots.len(), self.slots.iter());
+        enc.write_seq(self.slot_indices.len(), self.slot_indices.iter());
+        enc.write_seq(self.values.len(), self.values.iter());
+    }
+}
+
+impl<T: Decode + Default> Decode for DenseSlotMap<T> {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let mut slots: ToyVec<DenseSlot> = dec.read_seq()?;
+        let slot_indices: ToyVec<usize> = dec.read_seq()?;
+        let values = dec.read_seq()?;
+        if slot_indices.len() != values.len() {
+            return Err(DecodeError::Inconsistent(
+                "numbers of slot indices and values differ",
+            ));
+        }
+        // 各要素は別々のスロットから指されていなければならない
+        for (dense_index, &index) in slot_indices.iter().enumerate() {
+            match slots.get_mut(index) {
+                Some(slot) if slot.dense_index.is_none() => slot.dense_index = Some(dense_index),
+                _ => {
+                    return Err(DecodeError::Inconsistent(
+                        "slot index is out of bounds or duplicated",
+                    ))
+                }
+            }
+        }
+        let mut free_head = None;
+        for index in (0..slots.len()).rev() {
+            if let Some(slot) = slots.get_mut(index) {
+                if slot.dense_index.is_none() {
+                    slot.next_free = free_head;
+                    free_head = Some(index);
+                }
+            }
+        }
+        Ok(Self {
+            slots,
+            slot_indices,
+            values,
+            free_head,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DenseSlotMap, ToySlotMap};
+    use crate::codec::{from_bytes, to_bytes, DecodeError, Encoder};
+
+    #[test]
+    fn test_stale_key() {
+        let mut map = ToySlotMap::new();
+        let a = map.insert("Java Finch");
+        let b = map.insert("Budgerigar");
+
+        assert_eq!(map.remove(a), Some("Java Finch"));
+        // 削除済みのキーでは取得も削除もできない
+        assert_eq!(map.get(a), None);
+        assert_eq!(map.remove(a), None);
+
+        // 空いたスロットは再利用されるが、世代が違うので古いキーとは区別される
+        let c = map.insert("Canary");
+        assert_eq!(c.index(), a.index());
+        assert_ne!(c, a);
+        assert_eq!(map.get(a), None);
+        assert_eq!(map.get(c), Some(&"Canary"));
+        assert_eq!(map.get(b), Some(&"Budgerigar"));
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_iter() {
+        let mut map = ToySlotMap::new();
+        let keys: Vec<_> = (0..5).map(|i| map.insert(i)).collect();
+        map.remove(keys[1]);
+        map.remove(keys[3]);
+
+        for (_, v) in map.iter_mut() {
+            *v *= 10;
+        }
+        let items: Vec<_> = map.iter().map(|(k, v)| (k, *v)).collect();
+        assert_eq!(items, vec![(keys[0], 0), (keys[2], 20), (keys[4], 40)]);
+
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.get(keys[0]), None);
+    }
+
+    #[test]
+    fn test_dense_slot_map() {
+        let mut map = DenseSlotMap::new();
+        let a = map.insert(1);
+        let b = map.insert(2);
+        let c = map.insert(3);
+
+        // 先頭を削除すると末尾の要素が詰められるが、キーは有効なまま
+        assert_eq!(map.remove(a), Some(1));
+        assert_eq!(map.values(), &[3, 2]);
+        assert_eq!(map.get(c), Some(&3));
+        assert_eq!(map.get(b), Some(&2));
+        assert_eq!(map.get(a), None);
+
+        let d = map.insert(4);
+        assert_ne!(d, a);
+        if let Some(v) = map.get_mut(d) {
+            *v += 10;
+        }
+        let items: Vec<_> = map.iter().map(|(k, v)| (k, *v)).collect();
+        assert_eq!(items, vec![(c, 3), (b, 2), (d, 14)]);
+    }
+
+    #[test]
+    fn test_codec() {
+        let mut map = ToySlotMap::new();
+        let keys: Vec<_> = (0..4).map(|i| map.insert(i.to_string())).collect();
+        map.remove(keys[1]);
+        let mut decoded = from_bytes::<ToySlotMap<String>>(to_bytes(&map).as_slice()).unwrap();
+        // 読み込んだ後も、キーはそのまま使え、削除済みのキーは無効のまま
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(decoded.get(keys[3]).map(String::as_str), Some("3"));
+        assert_eq!(decoded.get(keys[1]), None);
+        let reused = decoded.insert("new".to_string());
+        assert_eq!(reused.index(), keys[1].index());
+        assert_ne!(reused, keys[1]);
+
+        let mut dense = DenseSlotMap::new();
+        let keys: Vec<_> = (0..4).map(|i| dense.insert(i * 10)).collect();
+        dense.remove(keys[0]);
+        let mut decoded = from_bytes::<DenseSlotMap<i32>>(to_bytes(&dense).as_slice()).unwrap();
+        assert_eq!(decoded.values(), dense.values());
+        assert!(decoded.iter().eq(dense.iter()));
+        assert_eq!(decoded.get(keys[0]), None);
+        assert_eq!(decoded.insert(50).index(), keys[0].index());
+
+        // 同じスロットを2つの要素が指している
+        let mut enc = Encoder::new();
+        enc.write_seq(1, &[0u64]);
+        enc.write_seq(2, &[0usize, 0]);
+        enc.write_seq(2, &[1i32, 2]);
+        assert!(matches!(
+            from_bytes::<DenseSlotMap<i32>>(enc.finish().as_slice()),
+            Err(DecodeError::Inconsistent(_))
+        ));
+    }
+}
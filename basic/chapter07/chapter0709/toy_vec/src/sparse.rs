@@ -0,0 +1,345 @@
+use crate::codec::{Decode, DecodeError, Decoder, Encode, Encoder};
+use crate::ToyVec;
+use std::ops::{Add, Mul};
+
+// ほとんどの要素が`T::default()`（ゼロ）であるベクタ
+// ゼロでない要素だけを、インデックスの昇順に並べた`indices`と`values`の組で持つ
+pub struct SparseToyVec<T> {
+    // ゼロでない要素のインデックス。昇順に並んでいる
+    indices: ToyVec<usize>,
+    // `indices`の同じ位置に対応する要素の値
+    values: ToyVec<T>,
+    // ベクタの長さ（ゼロの要素も含めた次元）
+    len: usize,
+    // `get`でゼロの要素への参照を返すための値
+    zero: T,
+}
+
+impl<T: Default + PartialEq> SparseToyVec<T> {
+    // すべての要素がゼロの、長さ`len`のベクタを作る
+    pub fn new(len: usize) -> Self {
+        Self {
+            indices: ToyVec::new(),
+            values: ToyVec::new(),
+            len,
+            zero: T::default(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // ゼロでない要素の数
+    pub fn nnz(&self) -> usize {
+        self.indices.len()
+    }
+
+    // `index`の要素への参照を返す。ゼロの要素なら`T::default()`への参照を返す
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        match self.position(index) {
+            Ok(pos) => self.values.get(pos),
+            Err(_) => Some(&self.zero),
+        }
+    }
+
+    // `index`の要素を設定する。ゼロを設定すると、その要素は格納しなくなる
+    pub fn set(&mut self, index: usize, value: T) {
+        assert!(
+            index < self.len,
+            "index out of bounds: the len is {} but the index is {}",
+            self.len,
+            index
+        );
+        match self.position(index) {
+            Ok(pos) if value == self.zero => {
+                self.indices.remove(pos);
+                self.values.remove(pos);
+            }
+            Ok(pos) => {
+                if let Some(slot) = self.values.get_mut(pos) {
+                    *slot = value;
+                }
+            }
+            Err(_) if value == self.zero => {}
+            Err(pos) => {
+                self.indices.insert(pos, index);
+                self.values.insert(pos, value);
+            }
+        }
+    }
+
+    // ゼロでない要素を`(インデックス, 値)`の組でインデックスの昇順に返すイテレータを作る
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            indices: self.indices.as_slice().iter(),
+            values: self.values.as_slice().iter(),
+        }
+    }
+
+    // 密なベクタからゼロでない要素だけを取り出して作る
+    pub fn from_dense(dense: &ToyVec<T>) -> Self
+    where
+        T: Clone,
+    {
+        let mut sparse = Self::new(dense.len());
+        for (index, value) in dense.iter().enumerate() {
+            if *value != sparse.zero {
+                sparse.indices.push(index);
+                sparse.values.push(value.clone());
+            }
+        }
+        sparse
+    }
+
+    // ゼロの要素も含めた密なベクタに変換する
+    pub fn to_dense(&self) -> ToyVec<T>
+    where
+        T: Clone,
+    {
+        let mut dense = ToyVec::with_capacity(self.len);
+        for _ in 0..self.len {
+            dense.push(T::default());
+        }
+        for (index, value) in self.iter() {
+            if let Some(slot) = dense.get_mut(index) {
+                *slot = value.clone();
+            }
+        }
+        dense
+    }
+
+    // 要素ごとの和。両方のゼロでない要素をインデックス順にマージする
+    pub fn add(&self, other: &Self) -> Self
+    where
+        T: Add<Output = T> + Clone,
+    {
+        self.assert_same_len(other);
+        let mut result = Self::new(self.len);
+        let (mut a, mut b) = (self.iter().peekable(), other.iter().peekable());
+        loop {
+            let (index, value) = match (a.peek(), b.peek()) {
+                (Some(&(i, x)), Some(&(j, y))) if i == j => {
+                    a.next();
+                    b.next();
+                    (i, x.clone() + y.clone())
+                }
+                (Some(&(i, x)), Some(&(j, _))) if i < j => {
+                    a.next();
+                    (i, x.clone())
+                }
+                (_, Some(&(j, y))) => {
+                    b.next();
+                    (j, y.clone())
+                }
+                (Some(&(i, x)), None) => {
+                    a.next();
+                    (i, x.clone())
+                }
+                (None, None) => break,
+            };
+            // 打ち消し合ってゼロになった要素は格納しない
+            if value != result.zero {
+                result.indices.push(index);
+                result.values.push(value);
+            }
+        }
+        result
+    }
+
+    // 要素ごとの積。どちらもゼロでない要素だけを掛け合わせる
+    pub fn mul(&self, other: &Self) -> Self
+    where
+        T: Mul<Output = T> + Clone,
+    {
+        self.assert_same_len(other);
+        let mut result = Self::new(self.len);
+        for (index, x, y) in self.intersection(other) {
+            let value = x.clone() * y.clone();
+            if value != result.zero {
+                result.indices.push(index);
+                result.values.push(value);
+            }
+        }
+        result
+    }
+
+    // 内積
+    pub fn dot(&self, other: &Self) -> T
+    where
+        T: Add<Output = T> + Mul<Output = T> + Clone,
+    {
+        self.assert_same_len(other);
+        self.intersection(other)
+            .fold(T::default(), |acc, (_, x, y)| acc + x.clone() * y.clone())
+    }
+
+    // 両方でゼロでない要素を`(インデックス, 自分の値, 相手の値)`で返す
+    fn intersection<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = (usize, &'a T, &'a T)> {
+        let mut b = other.iter().peekable();
+        self.iter().filter_map(move |(i, x)| {
+            while b.peek().is_some_and(|&(j, _)| j < i) {
+                b.next();
+            }
+            match b.peek() {
+                Some(&(j, y)) if j == i => Some((i, x, y)),
+                _ => None,
+            }
+        })
+    }
+
+    fn position(&self, index: usize) -> Result<usize, usize> {
+        self.indices.as_slice().binary_search(&index)
+    }
+
+    fn assert_same_len(&self, other: &Self) {
+        assert_eq!(
+            self.len, other.len,
+            "sparse vectors must have the same length"
+        );
+    }
+}
+
+pub struct Iter<'vec, T> {
+    indices: std::slice::Iter<'vec, usize>,
+    values: std::slice::Iter<'vec, T>,
+}
+
+impl<'vec, T> Iterator for Iter<'vec, T> {
+    type Item = (usize, &'vec T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some((*self.indices.next()?, self.values.next()?))
+    }
+}
+
+// バイナリ形式では長さ、ゼロでない要素のインデックス、その値の順に書く
+impl<T: Encode + Default> Encode for SparseToyVec<T> {
+    fn encode(&self, enc: &mut Encoder) {
+        self.len.encode(enc);
+        enc.write_seq(self.indices.len(), self.indices.iter());
+        enc.write_seq(self.values.len(), self.values.iter());
+    }
+}
+
+impl<T: Decode + Default + PartialEq> Decode for SparseToyVec<T> {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DecodeError> {
+        let len = usize::decode(dec)?;
+        let indices: ToyVec<usize> = dec.read_seq()?;
+        let values: ToyVec<T> = dec.read_seq()?;
+        if indices.len() != values.len() {
+            return Err(DecodeError::Inconsistent(
+                "numbers of indices and values differ",
+            ));
+        }
+        // `get`や`set`は二分探索するので、インデックスは範囲内で狭義単調増加でなければならない
+        let indices_sorted = indices.as_slice().windows(2).all(|w| w[0] < w[1]);
+        if !indices_sorted || indices.iter().any(|&i| i >= len) {
+            return Err(DecodeError::Inconsistent(
+                "indices are not sorted or out of bounds",
+            ));
+        }
+        let zero = T::default();
+        if values.iter().any(|value| *value == zero) {
+            return Err(DecodeError::Inconsistent("zero value is stored"));
+        }
+        Ok(Self {
+            indices,
+            values,
+            len,
+            zero,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SparseToyVec;
+    use crate::codec::{from_bytes, to_bytes, DecodeError, Encoder};
+    use crate::ToyVec;
+
+    #[test]
+    fn test_get_set() {
+        let mut v = SparseToyVec::new(1_000_000);
+        v.set(500_000, 2.5);
+        v.set(3, 1.0);
+        v.set(999_999, -1.0);
+        assert_eq!(v.nnz(), 3);
+        assert_eq!(v.get(3), Some(&1.0));
+        assert_eq!(v.get(4), Some(&0.0));
+        assert_eq!(v.get(1_000_000), None);
+        assert!(v.iter().map(|(i, _)| i).eq(vec![3, 500_000, 999_999]));
+
+        // ゼロを設定した要素は格納しなくなる
+        v.set(3, 0.0);
+        v.set(4, 0.0);
+        assert_eq!(v.nnz(), 2);
+        v.set(500_000, 7.0);
+        assert_eq!(v.get(500_000), Some(&7.0));
+    }
+
+    #[test]
+    fn test_dense_conversion() {
+        let mut dense = ToyVec::new();
+        for x in &[0, 3, 0, 0, 5, 0] {
+            dense.push(*x);
+        }
+        let sparse = SparseToyVec::from_dense(&dense);
+        assert_eq!(sparse.len(), 6);
+        assert_eq!(sparse.nnz(), 2);
+        assert_eq!(sparse.to_dense(), dense);
+    }
+
+    #[test]
+    fn test_codec() {
+        let mut v = SparseToyVec::new(1_000_000);
+        v.set(999_999, -3i64);
+        v.set(10, 7);
+        let bytes = to_bytes(&v);
+        assert!(bytes.len() < 16);
+        let decoded = from_bytes::<SparseToyVec<i64>>(bytes.as_slice()).unwrap();
+        assert_eq!(decoded.len(), 1_000_000);
+        assert!(decoded.iter().eq(v.iter()));
+
+        // インデックスが昇順でない
+        let mut enc = Encoder::new();
+        enc.write_varint(10);
+        enc.write_seq(2, &[5usize, 3]);
+        enc.write_seq(2, &[1u8, 2]);
+        assert!(matches!(
+            from_bytes::<SparseToyVec<u8>>(enc.finish().as_slice()),
+            Err(DecodeError::Inconsistent(_))
+        ));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let mut a = SparseToyVec::new(10);
+        let mut b = SparseToyVec::new(10);
+        a.set(1, 2);
+        a.set(4, 3);
+        a.set(7, 1);
+        b.set(4, 5);
+        b.set(7, -1);
+        b.set(9, 4);
+
+        let sum = a.add(&b);
+        assert!(sum.iter().eq(vec![(1, &2), (4, &8), (9, &4)]));
+        let product = a.mul(&b);
+        assert!(product.iter().eq(vec![(4, &15), (7, &-1)]));
+        assert_eq!(a.dot(&b), 14);
+    }
+
+    #[test]
+    #[should_panic(expected = "same length")]
+    fn test_length_mismatch_panics() {
+        SparseToyVec::<i32>::new(3).dot(&SparseToyVec::new(4));
+    }
+}
@@ -0,0 +1,523 @@
+use crate::{IterMut, ToyVec};
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::iter::Enumerate;
+use std::ops::{Deref, DerefMut};
+
+// 履歴が持つ要素の数のデフォルトの上限
+const DEFAULT_HISTORY_LIMIT: usize = 1 << 16;
+
+// `VersionedToyVec`に対する1回の変更。変更前後の値を持つので逆向きにも適用できる
+#[derive(Debug, Clone, PartialEq)]
+pub enum Op<T> {
+    Push(T),
+    Pop(T),
+    Insert { index: usize, element: T },
+    Remove { index: usize, element: T },
+    Set { index: usize, old: T, new: T },
+    // `with_iter_mut`の1回分のように、まとめて1回として扱う変更
+    Batch(Vec<Op<T>>),
+}
+
+impl<T: Clone + Default> Op<T> {
+    // この操作を打ち消す操作を返す
+    pub fn inverse(&self) -> Op<T> {
+        match self {
+            Op::Push(element) => Op::Pop(element.clone()),
+            Op::Pop(element) => Op::Push(element.clone()),
+            Op::Insert { index, element } => Op::Remove {
+                index: *index,
+                element: element.clone(),
+            },
+            Op::Remove { index, element } => Op::Insert {
+                index: *index,
+                element: element.clone(),
+            },
+            Op::Set { index, old, new } => Op::Set {
+                index: *index,
+                old: new.clone(),
+                new: old.clone(),
+            },
+            Op::Batch(ops) => Op::Batch(ops.iter().rev().map(Op::inverse).collect()),
+        }
+    }
+
+    fn apply(&self, vec: &mut ToyVec<T>) {
+        match self {
+            Op::Push(element) => vec.push(element.clone()),
+            Op::Pop(_) => {
+                vec.pop();
+            }
+            Op::Insert { index, element } => vec.insert(*index, element.clone()),
+            Op::Remove { index, .. } => {
+                vec.remove(*index);
+            }
+            Op::Set { index, new, .. } => {
+                if let Some(slot) = vec.get_mut(*index) {
+                    *slot = new.clone();
+                }
+            }
+            Op::Batch(ops) => {
+                for op in ops {
+                    op.apply(vec);
+                }
+            }
+        }
+    }
+
+    // この操作が持っている要素の数。履歴のメモリの上限はこの合計で判定する
+    fn size(&self) -> usize {
+        match self {
+            Op::Push(_) | Op::Pop(_) | Op::Insert { .. } | Op::Remove { .. } => 1,
+            Op::Set { .. } => 2,
+            Op::Batch(ops) => ops.iter().map(Op::size).sum(),
+        }
+    }
+}
+
+// 変更を記録し、取り消し（undo）とやり直し（redo）ができる`ToyVec`
+// 操作を適用するたびにバージョン番号が1つ進む。名前付きのチェックポイントでバージョンを覚えておける
+pub struct VersionedToyVec<T> {
+    vec: ToyVec<T>,
+    // 記録した操作。先頭から`cursor`個が適用済みで、残りはやり直しできる操作
+    // 上限を超えたら古い操作から捨てるので、先頭からの削除が速い`VecDeque`を使う
+    history: VecDeque<Op<T>>,
+    cursor: usize,
+    // `history`の先頭の操作を適用する前のバージョン番号（捨てた操作の数）
+    base: usize,
+    // `history`の操作が持っている要素の合計と、その上限
+    history_size: usize,
+    history_limit: usize,
+    checkpoints: HashMap<String, usize>,
+}
+
+impl<T: Clone + Default> VersionedToyVec<T> {
+    pub fn new() -> Self {
+        Self::with_history_limit(DEFAULT_HISTORY_LIMIT)
+    }
+
+    // 履歴が持つ要素の数の上限を指定して作る
+    // 操作ごとに持っている要素の数（`Set`は変更前後の2つ、`Batch`は中身の合計）を数え、
+    // 合計が上限を超えると古い操作から捨てるので、履歴が使うメモリは上限に比例する量で収まる
+    // 1回で上限を超える操作は、適用はするが取り消せない
+    pub fn with_history_limit(history_limit: usize) -> Self {
+        Self {
+            vec: ToyVec::new(),
+            history: VecDeque::new(),
+            cursor: 0,
+            base: 0,
+            history_size: 0,
+            history_limit,
+            checkpoints: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.vec.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vec.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.vec.get(index)
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        self.vec.as_slice()
+    }
+
+    pub fn iter(&self) -> crate::Iter<'_, T> {
+        self.vec.iter()
+    }
+
+    // 履歴が持っている要素の数
+    pub fn history_size(&self) -> usize {
+        self.history_size
+    }
+
+    // 現在のバージョン番号
+    pub fn version(&self) -> usize {
+        self.base + self.cursor
+    }
+
+    pub fn push(&mut self, element: T) {
+        self.apply(Op::Push(element));
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let element = self.vec.get(self.vec.len().checked_sub(1)?)?.clone();
+        self.apply(Op::Pop(element.clone()));
+        Some(element)
+    }
+
+    pub fn insert(&mut self, index: usize, element: T) {
+        assert!(
+            index <= self.len(),
+            "insertion index (is {}) should be <= len (is {})",
+            index,
+            self.len()
+        );
+        self.apply(Op::Insert { index, element });
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        let element = self.vec.get(index)?.clone();
+        self.apply(Op::Remove {
+            index,
+            element: element.clone(),
+        });
+        Some(element)
+    }
+
+    // `index`の要素を置き換え、置き換える前の値を返す
+    pub fn set(&mut self, index: usize, element: T) -> Option<T> {
+        let old = self.vec.get(index)?.clone();
+        self.apply(Op::Set {
+            index,
+            old: old.clone(),
+            new: element,
+        });
+        Some(old)
+    }
+
+    // 直前の操作を取り消す。取り消せる操作がなければ`false`を返す
+    pub fn undo(&mut self) -> bool {
+        if self.cursor == 0 {
+            return false;
+        }
+        self.cursor -= 1;
+        self.history[self.cursor].inverse().apply(&mut self.vec);
+        true
+    }
+
+    // 取り消した操作をやり直す。やり直せる操作がなければ`false`を返す
+    pub fn redo(&mut self) -> bool {
+        if self.cursor == self.history.len() {
+            return false;
+        }
+        self.history[self.cursor].apply(&mut self.vec);
+        self.cursor += 1;
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.cursor > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.cursor < self.history.len()
+    }
+
+    // 現在のバージョンに名前を付ける。同じ名前があれば上書きする
+    pub fn checkpoint(&mut self, name: &str) {
+        self.checkpoints.insert(name.to_string(), self.version());
+    }
+
+    // 名前を付けたバージョンまで取り消し、またはやり直す
+    // チェックポイントがない、またはその操作がすでに履歴から捨てられていれば`false`を返す
+    pub fn restore(&mut self, name: &str) -> bool {
+        let target = match self.checkpoints.get(name) {
+            Some(&version) => version,
+            None => return false,
+        };
+        while self.version() > target {
+            self.undo();
+        }
+        while self.version() < target {
+            self.redo();
+        }
+        true
+    }
+
+    // チェックポイント`from`の状態を`to`の状態にするための操作を順に返す
+    // どちらかのチェックポイントがない、または履歴から捨てられていれば`None`を返す
+    pub fn diff(&self, from: &str, to: &str) -> Option<Vec<Op<T>>> {
+        let from = self.checkpoints.get(from)? - self.base;
+        let to = self.checkpoints.get(to)? - self.base;
+        if from <= to {
+            Some(self.history.range(from..to).cloned().collect())
+        } else {
+            // 逆向きなら、間の操作を後ろから打ち消していく
+            Some(
+                self.history
+                    .range(to..from)
+                    .rev()
+                    .map(Op::inverse)
+                    .collect(),
+            )
+        }
+    }
+
+    // `IterMut`で要素を書き換え、書き換えた要素を1回の操作として記録する
+    // 要素は`RecordedMut`越しに渡す。可変で参照されたときだけ元の値を複製しておき、
+    // `RecordedMut`をドロップするときに比較して、変わっていれば記録する
+    // （`RecordedMut`はクロージャの外へ持ち出せないので、`mem::forget`しない限り記録漏れは起きない）
+    pub fn with_iter_mut<F>(&mut self, f: F)
+    where
+        F: FnOnce(RecordingIterMut<'_, T>),
+        T: PartialEq,
+    {
+        let ops = RefCell::new(Vec::new());
+        f(RecordingIterMut {
+            iter: self.vec.iter_mut().enumerate(),
+            ops: &ops,
+        });
+
+        let ops = ops.into_inner();
+        if !ops.is_empty() {
+            self.record(Op::Batch(ops));
+        }
+    }
+
+    fn apply(&mut self, op: Op<T>) {
+        op.apply(&mut self.vec);
+        self.record(op);
+    }
+
+    // 適用済みの操作を履歴に追加する
+    fn record(&mut self, op: Op<T>) {
+        // やり直せる操作と、それより先のチェックポイントは捨てる
+        for discarded in self.history.drain(self.cursor..) {
+            self.history_size -= discarded.size();
+        }
+        let version = self.version();
+        self.checkpoints.retain(|_, v| *v <= version);
+
+        self.history_size += op.size();
+        self.history.push_back(op);
+        self.cursor += 1;
+
+        // 上限を超えた分は古い操作から捨てる。捨てた操作より前のチェックポイントには戻れない
+        while self.history_size > self.history_limit {
+            if let Some(discarded) = self.history.pop_front() {
+                self.history_size -= discarded.size();
+            }
+            self.base += 1;
+            self.cursor -= 1;
+        }
+        let base = self.base;
+        self.checkpoints.retain(|_, v| *v >= base);
+    }
+}
+
+impl<T: Clone + Default> Default for VersionedToyVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// `with_iter_mut`のクロージャに渡すイテレータ
+pub struct RecordingIterMut<'a, T: Clone + PartialEq> {
+    iter: Enumerate<IterMut<'a, T>>,
+    ops: &'a RefCell<Vec<Op<T>>>,
+}
+
+impl<'a, T: Clone + PartialEq> Iterator for RecordingIterMut<'a, T> {
+    type Item = RecordedMut<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (index, element) = self.iter.next()?;
+        Some(RecordedMut {
+            index,
+            element,
+            old: None,
+            ops: self.ops,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+// `RecordingIterMut`が返す要素への可変参照。書き換えはドロップするときに記録する
+pub struct RecordedMut<'a, T: Clone + PartialEq> {
+    index: usize,
+    element: &'a mut T,
+    // 最初に可変で参照されたときの値
+    old: Option<T>,
+    ops: &'a RefCell<Vec<Op<T>>>,
+}
+
+impl<'a, T: Clone + PartialEq> Deref for RecordedMut<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.element
+    }
+}
+
+impl<'a, T: Clone + PartialEq> DerefMut for RecordedMut<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        if self.old.is_none() {
+            self.old = Some(self.element.clone());
+        }
+        self.element
+    }
+}
+
+impl<'a, T: Clone + PartialEq> Drop for RecordedMut<'a, T> {
+    fn drop(&mut self) {
+        if let Some(old) = self.old.take() {
+            if old != *self.element {
+                self.ops.borrow_mut().push(Op::Set {
+                    index: self.index,
+                    old,
+                    new: self.element.clone(),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Op, VersionedToyVec};
+
+    #[test]
+    fn test_undo_redo() {
+        let mut v = VersionedToyVec::new();
+        v.push(1);
+        v.push(2);
+        v.push(3);
+        v.insert(0, 0);
+        v.set(3, 30);
+        assert_eq!(v.remove(1), Some(1));
+        assert_eq!(v.pop(), Some(30));
+        assert_eq!(v.as_slice(), &[0, 2]);
+
+        assert!(v.undo());
+        assert_eq!(v.as_slice(), &[0, 2, 30]);
+        assert!(v.undo());
+        assert!(v.undo());
+        assert_eq!(v.as_slice(), &[0, 1, 2, 3]);
+        assert!(v.redo());
+        assert_eq!(v.as_slice(), &[0, 1, 2, 30]);
+
+        // 取り消した後に変更すると、やり直せる操作は捨てられる
+        v.push(4);
+        assert!(!v.redo());
+        while v.undo() {}
+        assert!(v.is_empty());
+        assert_eq!(v.version(), 0);
+    }
+
+    #[test]
+    fn test_iter_mut_is_recorded() {
+        let mut v = VersionedToyVec::new();
+        for i in 1..=5 {
+            v.push(i);
+        }
+        v.with_iter_mut(|iter| {
+            for mut i in iter {
+                if *i % 2 == 1 {
+                    *i *= 10;
+                }
+            }
+        });
+        assert_eq!(v.as_slice(), &[10, 2, 30, 4, 50]);
+
+        // 1回の`with_iter_mut`は1回で取り消せる
+        assert!(v.undo());
+        assert_eq!(v.as_slice(), &[1, 2, 3, 4, 5]);
+        assert!(v.redo());
+        assert_eq!(v.as_slice(), &[10, 2, 30, 4, 50]);
+    }
+
+    #[test]
+    fn test_checkpoints_and_diff() {
+        let mut v = VersionedToyVec::new();
+        v.push("a");
+        v.checkpoint("start");
+        v.push("b");
+        v.set(0, "A");
+        v.checkpoint("edited");
+
+        assert_eq!(
+            v.diff("start", "edited"),
+            Some(vec![
+                Op::Push("b"),
+                Op::Set {
+                    index: 0,
+                    old: "a",
+                    new: "A"
+                }
+            ])
+        );
+        assert_eq!(
+            v.diff("edited", "start"),
+            Some(vec![
+                Op::Set {
+                    index: 0,
+                    old: "A",
+                    new: "a"
+                },
+                Op::Pop("b")
+            ])
+        );
+        assert_eq!(v.diff("start", "missing"), None);
+
+        assert!(v.restore("start"));
+        assert_eq!(v.as_slice(), &["a"]);
+        assert!(v.restore("edited"));
+        assert_eq!(v.as_slice(), &["A", "b"]);
+    }
+
+    #[test]
+    fn test_history_limit() {
+        let mut v = VersionedToyVec::with_history_limit(3);
+        v.push(0);
+        v.checkpoint("old");
+        for i in 1..10 {
+            v.push(i);
+        }
+        // 上限を超えた古い操作は取り消せない
+        let mut undone = 0;
+        while v.undo() {
+            undone += 1;
+        }
+        assert_eq!(undone, 3);
+        assert_eq!(v.len(), 7);
+        assert!(!v.restore("old"));
+    }
+
+    #[test]
+    fn test_history_limit_counts_elements() {
+        let mut v = VersionedToyVec::with_history_limit(6);
+        for i in 0..5 {
+            v.push(i);
+        }
+        // 変更前後の値を持つ`Set`は2要素分として数える
+        v.set(0, 10);
+        assert_eq!(v.history_size(), 6);
+        let mut undone = 0;
+        while v.undo() {
+            undone += 1;
+        }
+        assert_eq!(undone, 5);
+        assert_eq!(v.as_slice(), &[0]);
+
+        // 書き換えた要素だけが記録される
+        while v.redo() {}
+        v.with_iter_mut(|iter| {
+            for mut i in iter {
+                if *i == 10 {
+                    *i = 100;
+                }
+                // 書き換えても元の値に戻せば記録しない
+                *i += 1;
+                *i -= 1;
+            }
+        });
+        assert_eq!(v.as_slice(), &[100, 1, 2, 3, 4]);
+        assert_eq!(v.history_size(), 6);
+
+        // 上限より大きい操作は適用するが、取り消せない
+        v.with_iter_mut(|iter| iter.for_each(|mut i| *i *= 2));
+        assert_eq!(v.as_slice(), &[200, 2, 4, 6, 8]);
+        assert_eq!(v.history_size(), 0);
+        assert!(!v.undo());
+    }
+}
@@ -0,0 +1,46 @@
+/* toy_vec.h の宣言どおりに関数を呼べることを確かめるCのテスト */
+#include <assert.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "toy_vec.h"
+
+int main(void) {
+    ToyVecI32 *v = toyvec_i32_new();
+    for (int32_t i = 0; i < 1000; i++) {
+        toyvec_i32_push(v, i * 2);
+    }
+    assert(toyvec_i32_len(v) == 1000);
+
+    /* NDEBUGでassertが消えても呼び出しは残るよう、呼んでから結果を確かめる */
+    int32_t value = -1;
+    bool found = toyvec_i32_get(v, 10, &value);
+    assert(found && value == 20);
+    found = toyvec_i32_get(v, 1000, &value);
+    assert(!found && value == 20);
+    bool popped = toyvec_i32_pop(v, &value);
+    assert(popped && value == 1998);
+
+    const int32_t *p = toyvec_i32_as_ptr(v);
+    long sum = 0;
+    for (size_t i = 0; i < toyvec_i32_len(v); i++) {
+        sum += p[i];
+    }
+    assert(sum == 999L * 998L);
+    toyvec_i32_free(v);
+    toyvec_i32_free(NULL);
+
+    ToyVecBytes *b = toyvec_bytes_new();
+    const char *text = "usagi";
+    toyvec_bytes_extend(b, (const uint8_t *)text, strlen(text));
+    toyvec_bytes_push(b, '!');
+    assert(toyvec_bytes_len(b) == 6);
+    assert(memcmp(toyvec_bytes_as_ptr(b), "usagi!", 6) == 0);
+    uint8_t last = 0;
+    popped = toyvec_bytes_pop(b, &last);
+    assert(popped && last == '!');
+    toyvec_bytes_free(b);
+
+    printf("ok\n");
+    return 0;
+}
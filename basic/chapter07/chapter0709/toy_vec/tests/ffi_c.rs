@@ -0,0 +1,66 @@
+// `include/toy_vec.h`を使うCのプログラムをシステムの`cc`でコンパイルし、
+// このクレートの静的ライブラリとリンクして実行する
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// 静的ライブラリは`ffi`フィーチャーを有効にしたときだけ作るので、テスト用のディレクトリに別途ビルドする
+fn build_static_library(out_dir: &Path) -> PathBuf {
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let target_dir = out_dir.join("ffi");
+    let status = Command::new(cargo)
+        .args([
+            "rustc",
+            "--lib",
+            "--features",
+            "ffi",
+            "--crate-type",
+            "staticlib",
+        ])
+        .arg("--manifest-path")
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .status()
+        .expect("failed to run cargo");
+    assert!(status.success(), "failed to build the static library");
+    target_dir.join("debug").join("libtoy_vec.a")
+}
+
+#[test]
+fn test_c_program() {
+    if Command::new("cc").arg("--version").output().is_err() {
+        eprintln!("skipping: `cc` is not available");
+        return;
+    }
+
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let out_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let lib = build_static_library(&out_dir);
+
+    let program = out_dir.join("test_toy_vec");
+    let status = Command::new("cc")
+        .arg("-std=c99")
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(manifest_dir.join("include"))
+        .arg(manifest_dir.join("tests/c/test_toy_vec.c"))
+        .arg(&lib)
+        .args(["-lpthread", "-ldl", "-lm"])
+        .arg("-o")
+        .arg(&program)
+        .status()
+        .expect("failed to run cc");
+    assert!(status.success(), "failed to compile the C test program");
+
+    let output = Command::new(&program)
+        .output()
+        .expect("failed to run the C test program");
+    assert!(
+        output.status.success(),
+        "C test program failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+}
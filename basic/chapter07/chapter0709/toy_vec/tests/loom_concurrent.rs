@@ -0,0 +1,59 @@
+// `ConcurrentToyVec`のアトミック操作をloomで検査する
+// RUSTFLAGS="--cfg loom" cargo test --test loom_concurrent --release
+#![cfg(loom)]
+
+use loom::sync::Arc;
+use loom::thread;
+use toy_vec::ConcurrentToyVec;
+
+// 2つのスレッドが同時に追加しても、どちらの要素も失われない
+// 最初のバケットの大きさは2なので、3つ目の要素でバケットの確保が競合する
+#[test]
+fn concurrent_push() {
+    loom::model(|| {
+        let v = Arc::new(ConcurrentToyVec::new());
+        v.push(0);
+
+        let handles: Vec<_> = (1..3)
+            .map(|i| {
+                let v = Arc::clone(&v);
+                thread::spawn(move || {
+                    v.push(i);
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+
+        let mut items: Vec<_> = v.iter().copied().collect();
+        items.sort();
+        assert_eq!(items, [0, 1, 2]);
+    });
+}
+
+// 追加と同時に読み出しても、書き込み途中の要素は見えず、見えた要素は正しい値になっている
+#[test]
+fn read_while_pushing() {
+    loom::model(|| {
+        let v = Arc::new(ConcurrentToyVec::new());
+        let writer = {
+            let v = Arc::clone(&v);
+            thread::spawn(move || {
+                for i in 0..3 {
+                    v.push(i * 10);
+                }
+            })
+        };
+
+        let seen: Vec<_> = v.iter().copied().collect();
+        assert!(seen.len() <= 3);
+        for (i, value) in seen.into_iter().enumerate() {
+            assert_eq!(value, i * 10);
+        }
+
+        writer.join().unwrap();
+        assert_eq!(v.len(), 3);
+        assert!(v.iter().copied().eq([0, 10, 20]));
+    });
+}
@@ -0,0 +1,31 @@
+// `toy_vec_repl`に`tests/repl/*.txt`のスクリプトを実行させ、出力を`*.out`と比べるテスト
+// キャパシティの増え方を変えたときなど、出力が変わるのが正しい場合は次のように作り直す
+//   cargo run --bin toy_vec_repl -- --script tests/repl/growth.txt > tests/repl/growth.out
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+fn check_golden(name: &str) {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/repl");
+    let script = dir.join(format!("{}.txt", name));
+    let expected = fs::read_to_string(dir.join(format!("{}.out", name))).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_toy_vec_repl"))
+        .arg("--script")
+        .arg(&script)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let actual = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(actual, expected, "output of {} differs", script.display());
+}
+
+#[test]
+fn test_growth() {
+    check_golden("growth");
+}
+
+#[test]
+fn test_reserve_and_errors() {
+    check_golden("reserve");
+}
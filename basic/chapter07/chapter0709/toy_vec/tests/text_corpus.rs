@@ -0,0 +1,112 @@
+// ギャップバッファとロープに、エディタで起きるような編集をまとめて加えるテスト
+// 大きめの文書の途中で挿入と削除を繰り返し、`Vec<char>`に同じ編集をした結果と比べる
+use toy_vec::{GapBuffer, ToyRope};
+
+const LINES: usize = 2_000;
+const EDITS: usize = 3_000;
+
+// 線形合同法による擬似乱数
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, bound: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 33) as usize) % bound.max(1)
+    }
+}
+
+fn corpus() -> String {
+    let words = ["うさぎ", "rabbit", "ぴょん", "carrot", "🥕", "hop"];
+    let mut rng = Lcg(42);
+    let mut text = String::new();
+    for line in 0..LINES {
+        for _ in 0..rng.next(12) {
+            text.push_str(words[rng.next(words.len())]);
+            text.push(' ');
+        }
+        text.push_str(&line.to_string());
+        text.push('\n');
+    }
+    text
+}
+
+enum Edit {
+    Insert(usize, &'static str),
+    Delete(usize),
+}
+
+fn edits(len: usize) -> Vec<Edit> {
+    let snippets = ["x", "ぴょん", "\n", "二羽\n三羽", "🥕🥕"];
+    let mut rng = Lcg(7);
+    let mut len = len;
+    (0..EDITS)
+        .map(|_| {
+            if rng.next(3) == 0 && len > 0 {
+                len -= 1;
+                Edit::Delete(rng.next(len))
+            } else {
+                let s = snippets[rng.next(snippets.len())];
+                let pos = rng.next(len + 1);
+                len += s.chars().count();
+                Edit::Insert(pos, s)
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_corpus_edits() {
+    let text = corpus();
+    let mut expected: Vec<char> = text.chars().collect();
+    let edits = edits(expected.len());
+
+    let mut gap = GapBuffer::from_text(&text);
+    let mut rope = ToyRope::from_text(&text);
+
+    for edit in &edits {
+        match *edit {
+            Edit::Insert(pos, s) => {
+                gap.move_to(pos);
+                gap.insert_str(s);
+                rope.move_to(pos);
+                rope.insert_str(s);
+
+                for (i, c) in s.chars().enumerate() {
+                    expected.insert(pos + i, c);
+                }
+            }
+            Edit::Delete(pos) => {
+                gap.move_to(pos + 1);
+                let from_gap = gap.delete_backward();
+                rope.move_to(pos + 1);
+                let from_rope = rope.delete_backward();
+
+                let c = expected.remove(pos);
+                assert_eq!(from_gap, Some(c));
+                assert_eq!(from_rope, Some(c));
+            }
+        }
+        assert_eq!(gap.cursor(), rope.cursor());
+    }
+
+    let expected: String = expected.into_iter().collect();
+    assert_eq!(gap.to_string(), expected);
+    assert_eq!(rope.to_string(), expected);
+
+    // 行と列による位置の計算も一致する
+    let lines: Vec<&str> = expected.split('\n').collect();
+    assert_eq!(gap.line_count(), lines.len());
+    assert_eq!(rope.line_count(), lines.len());
+    for line in (0..lines.len()).step_by(97) {
+        assert_eq!(rope.line(line).as_deref(), Some(lines[line]));
+        assert_eq!(gap.line(line).as_deref(), Some(lines[line]));
+        let col = lines[line].chars().count() / 2;
+        let pos = rope.position(line, col).unwrap();
+        assert_eq!(gap.position(line, col), Some(pos));
+        assert_eq!(rope.line_col(pos), (line, col));
+        assert_eq!(gap.line_col(pos), (line, col));
+    }
+}
@@ -0,0 +1,164 @@
+// `#[derive(ToySoa)]`を提供する手続き型マクロのクレート
+//
+// 構造体`Foo`にderiveすると、フィールドごとに`toy_vec::ToyVec`を1つずつ持つ
+// 「配列の構造体」（struct of arrays）である`FooToyVec`と、その1要素分の参照をまとめた`FooRef`を生成する
+// 同じフィールドの値がメモリ上で連続するので、1つのフィールドだけを走査する処理がキャッシュやSIMDと相性が良くなる
+//
+//   #[derive(ToySoa)]
+//   struct Particle { x: f32, y: f32 }
+//
+// から、次のようなコードを生成する
+//
+//   struct ParticleToyVec { x: ToyVec<f32>, y: ToyVec<f32> }
+//   struct ParticleRef<'__soa> { x: &'__soa f32, y: &'__soa f32 }
+//   impl ParticleToyVec {
+//       fn push(&mut self, value: Particle) { ... }
+//       fn get(&self, index: usize) -> Option<ParticleRef<'_>> { ... }
+//       fn x(&self) -> &[f32] { ... }
+//       fn x_mut(&mut self) -> &mut [f32] { ... }
+//       ...
+//   }
+//
+// フィールドごとのアクセサはフィールドと同じ名前になるので、`len`や`push`のような
+// 生成されるメソッドと同じ名前のフィールドを持つ構造体にはderiveできない
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, parse_quote, Data, DeriveInput, Fields, GenericParam, Lifetime,
+    LifetimeParam,
+};
+
+#[proc_macro_derive(ToySoa)]
+pub fn derive_toy_soa(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) if !fields.named.is_empty() => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "ToySoa can only be derived for structs with at least one named field",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "ToySoa can only be derived for structs",
+            ))
+        }
+    };
+
+    let vis = &input.vis;
+    let name = &input.ident;
+    let vec_name = format_ident!("{}ToyVec", name);
+    let ref_name = format_ident!("{}Ref", name);
+
+    let field_vis: Vec<_> = fields.iter().map(|f| &f.vis).collect();
+    let names: Vec<_> = fields.iter().filter_map(|f| f.ident.as_ref()).collect();
+    let mut_names: Vec<_> = names.iter().map(|n| format_ident!("{}_mut", n)).collect();
+    let tys: Vec<_> = fields.iter().map(|f| &f.ty).collect();
+    // どのフィールドの`ToyVec`も同じ長さなので、長さは先頭のフィールドから求める
+    let first = names[0];
+
+    // 構造体の定義には元の型パラメータとその境界をそのまま使う
+    let generics = &input.generics;
+    let (_, ty_generics, where_clause) = generics.split_for_impl();
+
+    // `ToyVec<T>`のメソッドは`T: Default`を要求するので、implには各フィールドの型の境界を加える
+    let mut impl_generics = input.generics.clone();
+    {
+        let predicates = &mut impl_generics.make_where_clause().predicates;
+        for ty in &tys {
+            predicates.push(parse_quote!(#ty: ::std::default::Default));
+        }
+    }
+    let (impl_generics, _, impl_where_clause) = impl_generics.split_for_impl();
+
+    // `FooRef`は`FooToyVec`を借用する期間を表すライフタイムを先頭に加える
+    let soa = Lifetime::new("'__soa", Span::call_site());
+    let mut ref_generics = input.generics.clone();
+    ref_generics
+        .params
+        .insert(0, GenericParam::Lifetime(LifetimeParam::new(soa.clone())));
+    let (_, ref_ty_generics, _) = ref_generics.split_for_impl();
+    let ref_where_clause = where_clause;
+
+    Ok(quote! {
+        #vis struct #vec_name #generics #where_clause {
+            #( #names: ::toy_vec::ToyVec<#tys>, )*
+        }
+
+        #vis struct #ref_name #ref_generics #ref_where_clause {
+            #( #field_vis #names: &#soa #tys, )*
+        }
+
+        impl #impl_generics #vec_name #ty_generics #impl_where_clause {
+            pub fn new() -> Self {
+                Self {
+                    #( #names: ::toy_vec::ToyVec::new(), )*
+                }
+            }
+
+            pub fn with_capacity(capacity: usize) -> Self {
+                Self {
+                    #( #names: ::toy_vec::ToyVec::with_capacity(capacity), )*
+                }
+            }
+
+            pub fn len(&self) -> usize {
+                self.#first.len()
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.#first.is_empty()
+            }
+
+            pub fn push(&mut self, value: #name #ty_generics) {
+                #( self.#names.push(value.#names); )*
+            }
+
+            pub fn pop(&mut self) -> ::std::option::Option<#name #ty_generics> {
+                if self.is_empty() {
+                    return ::std::option::Option::None;
+                }
+                ::std::option::Option::Some(#name {
+                    #( #names: self.#names.pop()?, )*
+                })
+            }
+
+            pub fn get<#soa>(&#soa self, index: usize) -> ::std::option::Option<#ref_name #ref_ty_generics> {
+                ::std::option::Option::Some(#ref_name {
+                    #( #names: self.#names.get(index)?, )*
+                })
+            }
+
+            pub fn iter<#soa>(&#soa self) -> impl ::std::iter::Iterator<Item = #ref_name #ref_ty_generics> + #soa {
+                (0..self.len()).filter_map(move |index| self.get(index))
+            }
+
+            #(
+                pub fn #names(&self) -> &[#tys] {
+                    self.#names.as_slice()
+                }
+
+                pub fn #mut_names(&mut self) -> &mut [#tys] {
+                    self.#names.as_mut_slice()
+                }
+            )*
+        }
+
+        impl #impl_generics ::std::default::Default for #vec_name #ty_generics #impl_where_clause {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+    })
+}
@@ -0,0 +1,94 @@
+use toy_vec::ToyVec;
+use toy_vec_derive::ToySoa;
+
+#[derive(ToySoa, Debug, PartialEq)]
+pub struct Particle {
+    pub x: f32,
+    pub y: f32,
+    pub mass: f64,
+}
+
+#[derive(ToySoa, Debug, PartialEq)]
+struct Tagged<T: Clone> {
+    id: u32,
+    value: T,
+}
+
+#[derive(ToySoa)]
+struct Named<'a, T>
+where
+    T: Copy,
+{
+    name: &'a str,
+    scores: ToyVec<T>,
+}
+
+#[test]
+fn test_push_get_and_slices() {
+    let mut particles = ParticleToyVec::new();
+    assert!(particles.is_empty());
+    for i in 0..4 {
+        particles.push(Particle {
+            x: i as f32,
+            y: -(i as f32),
+            mass: 1.5,
+        });
+    }
+    assert_eq!(particles.len(), 4);
+
+    let p = particles.get(2).unwrap();
+    assert_eq!((*p.x, *p.y, *p.mass), (2.0, -2.0, 1.5));
+    assert!(particles.get(4).is_none());
+
+    // 同じフィールドの値はスライスとして連続して並ぶ
+    assert_eq!(particles.x(), &[0.0, 1.0, 2.0, 3.0]);
+    for y in particles.y_mut() {
+        *y *= 10.0;
+    }
+    assert_eq!(particles.y(), &[0.0, -10.0, -20.0, -30.0]);
+    assert_eq!(particles.iter().map(|p| *p.mass).sum::<f64>(), 6.0);
+
+    assert_eq!(
+        particles.pop(),
+        Some(Particle {
+            x: 3.0,
+            y: -30.0,
+            mass: 1.5
+        })
+    );
+    assert_eq!(particles.len(), 3);
+}
+
+#[test]
+fn test_generic_parameters() {
+    let mut tagged = TaggedToyVec::default();
+    tagged.push(Tagged {
+        id: 7,
+        value: "ミニレッキス".to_string(),
+    });
+    tagged.push(Tagged {
+        id: 9,
+        value: "ライオンヘッド".to_string(),
+    });
+    let t: TaggedRef<'_, String> = tagged.get(1).unwrap();
+    assert_eq!((*t.id, t.value.as_str()), (9, "ライオンヘッド"));
+    assert_eq!(tagged.id(), &[7, 9]);
+}
+
+#[test]
+fn test_lifetime_parameters() {
+    let names = ["ドワーフホト".to_string(), "アンゴラ".to_string()];
+    let mut named = NamedToyVec::with_capacity(2);
+    for (i, name) in names.iter().enumerate() {
+        let mut scores = ToyVec::new();
+        scores.push(i as u8);
+        named.push(Named {
+            name: name.as_str(),
+            scores,
+        });
+    }
+    assert_eq!(named.name(), &["ドワーフホト", "アンゴラ"]);
+    let n = named.get(1).unwrap();
+    assert_eq!(*n.name, "アンゴラ");
+    assert_eq!(n.scores.get(0), Some(&1));
+}